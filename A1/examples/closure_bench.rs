@@ -0,0 +1,61 @@
+//! Times the closure size of every package in a Packages index, computed one package at a time with
+//! transitive_dep_solution and in bulk with the ClosureEngine, and checks that they agree.
+//!
+//!     cargo run --release --example closure_bench -- data/Packages
+
+use std::env;
+use std::process;
+use std::time::Instant;
+
+use rpkg::Packages;
+
+fn main() {
+    let args : Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        eprintln!("usage: {} <Packages file>", args[0]);
+        process::exit(2);
+    }
+    let mut packages = Packages::new();
+    let n = packages.parse_packages(&args[1]).unwrap_or_else(|e| {
+        eprintln!("couldn't load {}: {}", args[1], e);
+        process::exit(1);
+    });
+    println!("{} packages available", n);
+
+    let mut names : Vec<&str> = packages.get_package_names().into_iter()
+        .filter(|name| packages.get_available_debver(name).is_some())
+        .collect();
+    names.sort();
+
+    let start = Instant::now();
+    let one_by_one : Vec<usize> = names.iter().map(|name| packages.transitive_dep_solution(name).len()).collect();
+    let one_by_one_time = start.elapsed();
+    println!("transitive_dep_solution, one package at a time: {:.2?}", one_by_one_time);
+
+    let start = Instant::now();
+    let sizes = packages.closure_engine().closure_sizes();
+    let bulk : Vec<usize> = names.iter().map(|name| sizes[packages.find_package_num(name).unwrap() as usize]).collect();
+    let bulk_time = start.elapsed();
+    println!("ClosureEngine::closure_sizes: {:.2?} ({:.0}x)", bulk_time, one_by_one_time.as_secs_f64() / bulk_time.as_secs_f64());
+
+    let mismatches : Vec<&&str> = names.iter().zip(one_by_one.iter().zip(&bulk)).filter(|(_, (a, b))| a != b).map(|(name, _)| name).collect();
+    if !mismatches.is_empty() {
+        eprintln!("closure sizes differ for {} packages, e.g. {}", mismatches.len(), mismatches[0]);
+        process::exit(1);
+    }
+    println!("closure sizes agree for all {} packages", names.len());
+
+    // closure() keeps every closure it computes, so only check a sample of them
+    let mut engine = packages.closure_engine();
+    let mut checked = 0;
+    for name in names.iter().step_by(names.len() / 1000 + 1) {
+        let mut expected = packages.transitive_dep_solution(name);
+        expected.sort();
+        if engine.closure(packages.find_package_num(name).unwrap()) != expected {
+            eprintln!("closures differ for {}", name);
+            process::exit(1);
+        }
+        checked += 1;
+    }
+    println!("closures agree for a sample of {} packages", checked);
+}
@@ -3,6 +3,7 @@ use std::string::ParseError;
 use std::cmp::Ordering::{Less, Equal, Greater};
 use std::str::FromStr;
 
+#[derive(Clone, Copy)]
 pub enum VersionRelation {
     StrictlyLess, // <<
     LessOrEqual, // <=
@@ -74,16 +75,16 @@ impl FromStr for DebianVersionNum {
     }
 }
 
-impl Ord for DebianVersionNum {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+impl PartialOrd for DebianVersionNum {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl PartialOrd for DebianVersionNum {
+impl Ord for DebianVersionNum {
     // https://www.debian.org/doc/debian-policy/ch-controlfields.html#version
     // wow this is painful
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         fn extract_nonnum(s: &str) -> (&str,&str) {
             let first_digit = s.find(|c:char| c.is_ascii_digit());
             match first_digit {
@@ -156,8 +157,8 @@ impl PartialOrd for DebianVersionNum {
                 let (other_num, other_rest1) = extract_num(other_rest);
 
                 let (sn_i, on_i) = (
-                    match self_num.parse::<i32>() { Err(_) => 0, Ok(e) => e },
-                    match other_num.parse::<i32>() { Err(_) => 0, Ok(e) => e });
+                    self_num.parse::<i32>().unwrap_or_default(),
+                    other_num.parse::<i32>().unwrap_or_default());
 
                 if sn_i != on_i {
                     return sn_i.partial_cmp(&on_i).unwrap();
@@ -167,16 +168,16 @@ impl PartialOrd for DebianVersionNum {
         }
 
         let (epoch, other_epoch) = (
-            match self.epoch.parse::<i32>() { Err(_) => 0, Ok(e) => e },
-            match other.epoch.parse::<i32>() { Err(_) => 0, Ok(e) => e });
+            self.epoch.parse::<i32>().unwrap_or_default(),
+            other.epoch.parse::<i32>().unwrap_or_default());
         if epoch != other_epoch {
-            return Some(epoch.partial_cmp(&other_epoch).unwrap());
+            return epoch.cmp(&other_epoch);
         }
         let ups = debian_cmp(&self.upstream, &other.upstream);
         if ups != Equal {
-            return Some(ups);
+            return ups;
         }
-        return Some(debian_cmp(&self.debian, &other.debian));
+        return debian_cmp(&self.debian, &other.debian);
     }
 }
 
@@ -1 +1,19 @@
+#![allow(clippy::needless_return)]
+
+//! rpkg: a small package database for Debian-style `Packages` indexes.
+//!
+//! Load an index of available packages (and optionally the list of installed packages) into a
+//! [`Packages`], then run dependency queries on it:
+//!
+//! ```no_run
+//! let mut packages = rpkg::Packages::new();
+//! packages.parse_packages("data/Packages").unwrap();
+//! packages.parse_installed("data/installed-packages").unwrap();
+//! let solution = packages.transitive_dep_solution("bash");
+//! println!("{:?}", solution.iter().map(|p| packages.get_package_name(*p)).collect::<Vec<_>>());
+//! ```
+
 pub mod debversion;
+pub mod packages;
+
+pub use packages::Packages;
@@ -1,113 +1,472 @@
+#![allow(clippy::needless_return)]
+
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-use rpkg::debversion;
-use crate::packages::Packages;
+use std::collections::HashMap;
+use std::io;
 
-mod packages;
+use rpkg::debversion;
+use rpkg::Packages;
+use rpkg::packages::{alternative_policy, parse_install_requests, read_install_requests, AlternativePolicy, CommandError, GraphExported, GraphFormat,
+                     IndexKind, IndexLoaded, NoSuchPackage, OutputFormat, POLICY_NAMES, QueuedRequest, Render, SoftDependencies, UpgradableOptions,
+                     VerificationReport};
 
-fn check_syntax(n: usize, cmd_fragments:&Vec<&str>, arg: &str) -> bool {
-    let cmd : &str = &cmd_fragments.get(0).unwrap();
+fn check_syntax(format: OutputFormat, n: usize, cmd_fragments:&Vec<&str>, arg: &str) -> bool {
+    let cmd : &str = cmd_fragments.first().unwrap();
     if cmd_fragments.len() != n {
-        println!("syntax: {} {}", cmd, arg);
+        fail(format, format!("syntax: {} {}", cmd, arg));
         return false
     }
     return true
 }
 
-fn process_command(state: &mut Packages, cmdline: &str) -> bool {
+// splits a command's arguments into positional arguments and --flags; the flags in `valued` take the
+// following argument as their value, any other flag is a switch. Unknown flags are an error.
+struct CmdArgs<'a> {
+    positional : Vec<&'a str>,
+    flags : HashMap<&'a str, Option<&'a str>>,
+}
+
+fn parse_cmd_args<'a>(args: &[&'a str], valued: &[&str], switches: &[&str]) -> Result<CmdArgs<'a>, String> {
+    let mut result = CmdArgs { positional : vec![], flags : HashMap::new() };
+    let mut it = args.iter().filter(|a| !a.is_empty());
+    while let Some(arg) = it.next() {
+        if valued.contains(arg) {
+            let value = it.next().ok_or(format!("{} needs an argument", arg))?;
+            result.flags.insert(arg, Some(value));
+        } else if switches.contains(arg) {
+            result.flags.insert(arg, None);
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option {}", arg));
+        } else {
+            result.positional.push(arg);
+        }
+    }
+    return Ok(result);
+}
+
+// the alternative policy named by --policy, or the default one
+fn policy_arg(args: &CmdArgs, default: &str) -> Result<Box<dyn AlternativePolicy>, String> {
+    return alternative_policy(args.flags.get("--policy").copied().flatten().unwrap_or(default));
+}
+
+// prints a query result (or the no-such-package error) in the current output format
+fn show<R: Render>(format: OutputFormat, result: Result<R, NoSuchPackage>) {
+    match result {
+        Ok(report) => report.render_as(format, &mut io::stdout()).unwrap(),
+        Err(e) => show_error(format, &e)
+    }
+}
+
+// prints why a command failed in the current output format; in JSON, that is a document with an "error" field
+fn show_error(format: OutputFormat, error: &impl Render) {
+    error.render_as(format, &mut io::stdout()).unwrap();
+}
+
+// show_error for anything but an unknown package: bad syntax or arguments, or a file that can't be read or written
+fn fail(format: OutputFormat, message: impl Into<String>) {
+    show_error(format, &CommandError::new(message));
+}
+
+fn load_packages(state: &mut Packages, format: OutputFormat, filename: &str) {
+    match state.parse_packages(filename) {
+        Ok(n) => show(format, Ok(IndexLoaded { file : filename.to_string(), kind : IndexKind::Available, packages : n })),
+        Err(e) => fail(format, format!("couldn't load {}: {}", filename, e))
+    }
+}
+
+fn load_installed(state: &mut Packages, format: OutputFormat, filename: &str) {
+    match state.parse_installed(filename) {
+        Ok(n) => show(format, Ok(IndexLoaded { file : filename.to_string(), kind : IndexKind::Installed, packages : n })),
+        Err(e) => fail(format, format!("couldn't load {}: {}", filename, e))
+    }
+}
+
+fn load_sources(state: &mut Packages, format: OutputFormat, filename: &str) {
+    match state.parse_sources(filename) {
+        Ok(n) => show(format, Ok(IndexLoaded { file : filename.to_string(), kind : IndexKind::Sources, packages : n })),
+        Err(e) => fail(format, format!("couldn't load {}: {}", filename, e))
+    }
+}
+
+fn process_command(state: &mut Packages, format: &mut OutputFormat, cmdline: &str) -> bool {
     let cmd_fragments: Vec<&str> = cmdline.split(" ").collect();
     if cmdline.is_empty() { return false }
-    let cmd : &str = &cmd_fragments.get(0).unwrap();
+    let cmd : &str = cmd_fragments.first().unwrap();
     match cmd {
         "quit" => { 
             return true 
         },
         "load-csv" | "lc" => {
-            if !check_syntax(2, &cmd_fragments, "<csvfile-name>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<csvfile-name>") { return false; }
             let arg = cmd_fragments.get(1).unwrap();
-            state.parse_csv(arg)
+            match state.parse_csv(arg) {
+                Ok(n) => show(*format, Ok(IndexLoaded { file : arg.to_string(), kind : IndexKind::Available, packages : n })),
+                Err(e) => fail(*format, format!("couldn't load {}: {}", arg, e))
+            }
         }
         // parsers.rs
         "load-packages" | "lp" => {
-            if !check_syntax(2, &cmd_fragments, "<pkgfile-name>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkgfile-name>") { return false; }
             let arg = cmd_fragments.get(1).unwrap();
-            state.parse_packages(arg)
+            load_packages(state, *format, arg);
         }
         "load-installed" | "li" => {
-            if !check_syntax(2, &cmd_fragments, "<pkgfile-name>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkgfile-name>") { return false; }
+            let arg = cmd_fragments.get(1).unwrap();
+            load_installed(state, *format, arg);
+        }
+        "load-sources" | "ls" => {
+            if !check_syntax(*format, 2, &cmd_fragments, "<sourcesfile-name>") { return false; }
             let arg = cmd_fragments.get(1).unwrap();
-            state.parse_installed(arg)
+            load_sources(state, *format, arg);
         }
         // convenience function, also depends on parsers.rs
         "load-defaults" | "ld" => {
-            state.parse_packages("data/mirror.csclub.uwaterloo.ca_debian_dists_sid_main_binary-amd64_Packages");
-            state.parse_installed("data/installed-packages")
+            load_packages(state, *format, "data/mirror.csclub.uwaterloo.ca_debian_dists_sid_main_binary-amd64_Packages");
+            load_installed(state, *format, "data/installed-packages");
         }
 
         "info" => {
-            if !check_syntax(2, &cmd_fragments, "<pkg>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkg>") { return false; }
             let pkg = cmd_fragments.get(1).unwrap();
-            state.print_info(pkg)
+            show(*format, state.info(pkg));
         }
         "deps" => {
             // test: deps 0ad
-            if !check_syntax(2, &cmd_fragments, "<pkg>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkg>") { return false; }
             let pkg = cmd_fragments.get(1).unwrap();
-            state.print_deps(pkg)
+            show(*format, state.deps(pkg));
         }
 
         // deps-available.rs
         "deps-available" => {
             // test: deps-available 3depict
-            if !check_syntax(2, &cmd_fragments, "<pkg>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkg>") { return false; }
             let pkg = cmd_fragments.get(1).unwrap();
-            state.deps_available(pkg)
+            show(*format, state.deps_available(pkg));
         }
 
         // solvers.rs, and deps-available.rs for how-to-install
         "transitive-dep-solution" => {
             // test: transitive-dep-solution 0ad
-            if !check_syntax(2, &cmd_fragments, "<pkg>") { return false; }
-            let pkg = cmd_fragments.get(1).unwrap();
-            state.print_transitive_dep_solution(pkg)
+            let syntax = format!("syntax: transitive-dep-solution <pkg> [--versions] [--policy {}]", POLICY_NAMES.join("|"));
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--policy"], &["--versions"]) {
+                Ok(args) if args.positional.len() == 1 => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let policy = match policy_arg(&args, "first-listed") {
+                Ok(policy) => policy,
+                Err(e) => { fail(*format, e); return false }
+            };
+            if args.flags.contains_key("--versions") {
+                show(*format, state.versioned_dep_solution(args.positional[0], policy.as_ref()));
+            } else {
+                show(*format, state.transitive_deps(args.positional[0], policy.as_ref()));
+            }
         }
         "how-to-install" => {
-            if !check_syntax(2, &cmd_fragments, "<pkg>") { return false; }
+            let syntax = format!("syntax: how-to-install <pkg> [(<op> <version>)] [<pkg>...] [--from <file>] [--policy {}] [--with-recommends] [--with-suggests]",
+                POLICY_NAMES.join("|"));
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--policy", "--from"], &["--with-recommends", "--with-suggests"]) {
+                Ok(args) if !args.positional.is_empty() || args.flags.contains_key("--from") => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let policy = match policy_arg(&args, "highest-version") {
+                Ok(policy) => policy,
+                Err(e) => { fail(*format, e); return false }
+            };
+            let mut requests = match parse_install_requests(&args.positional) {
+                Ok(requests) => requests,
+                Err(e) => { fail(*format, e); return false }
+            };
+            if let Some(Some(fname)) = args.flags.get("--from") {
+                match read_install_requests(fname) {
+                    Ok(more) => requests.extend(more),
+                    Err(e) => { fail(*format, e); return false }
+                }
+            }
+            let soft = SoftDependencies {
+                recommends : args.flags.contains_key("--with-recommends"),
+                suggests : args.flags.contains_key("--with-suggests"),
+            };
+            show(*format, state.how_to_install(&requests, policy.as_ref(), soft));
+        }
+
+        "install-order" => {
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkg>") { return false; }
             let pkg = cmd_fragments.get(1).unwrap();
-            state.print_how_to_install(pkg)
+            show(*format, state.install_order(pkg));
+        }
+        "base-system" => {
+            let syntax = format!("syntax: base-system [--with-important] [--policy {}]", POLICY_NAMES.join("|"));
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--policy"], &["--with-important"]) {
+                Ok(args) if args.positional.is_empty() => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let policy = match policy_arg(&args, "highest-version") {
+                Ok(policy) => policy,
+                Err(e) => { fail(*format, e); return false }
+            };
+            show(*format, Ok(state.base_system(args.flags.contains_key("--with-important"), policy.as_ref())));
+        }
+        "why-not" => {
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkg>") { return false; }
+            let pkg = cmd_fragments.get(1).unwrap();
+            show(*format, state.why_not(pkg));
+        }
+        "why" => {
+            let syntax = "syntax: why <root> <target> [--all [--limit <n>]]";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--limit"], &["--all"]) {
+                Ok(args) if args.positional.len() == 2 => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let limit = match args.flags.get("--limit") {
+                Some(Some(n)) => match n.parse::<usize>() {
+                    Ok(n) if n > 0 => n,
+                    _ => { fail(*format, "--limit needs a positive number"); return false }
+                },
+                _ => 20
+            };
+            let limit = if args.flags.contains_key("--all") { limit } else { 1 };
+            show(*format, state.why(args.positional[0], args.positional[1], limit));
+        }
+        "export-graph" => {
+            let syntax = "syntax: export-graph <pkg> [--depth <n>] [--format dot|graphml] <file>";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--depth", "--format"], &[]) {
+                Ok(args) if args.positional.len() == 2 => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let (pkg, fname) = (args.positional[0], args.positional[1]);
+            let depth = match args.flags.get("--depth") {
+                Some(Some(d)) => match d.parse::<usize>() {
+                    Ok(d) => Some(d),
+                    Err(_) => { fail(*format, "--depth needs a number"); return false }
+                },
+                _ => None
+            };
+            let graph_format = match args.flags.get("--format") {
+                Some(Some(f)) => match f.parse::<GraphFormat>() {
+                    Ok(f) => f,
+                    Err(e) => { fail(*format, e); return false }
+                },
+                _ => GraphFormat::Dot
+            };
+            let graph = match state.dependency_graph(pkg, depth) {
+                Ok(graph) => graph,
+                Err(e) => { show_error(*format, &e); return false }
+            };
+            let written = std::fs::File::create(fname).and_then(|mut f| match graph_format {
+                GraphFormat::Dot => state.write_dot(&graph, &mut f),
+                GraphFormat::GraphMl => state.write_graphml(&graph, &mut f)
+            });
+            match written {
+                Ok(()) => show(*format, Ok(GraphExported { package : pkg.to_string(), file : fname.to_string(), nodes : graph.node_count(), edges : graph.edge_count() })),
+                Err(e) => fail(*format, format!("couldn't write {}: {}", fname, e))
+            }
+        }
+        "plan-remove" => {
+            let pkgs : Vec<&str> = cmd_fragments[1..].iter().copied().filter(|p| !p.is_empty()).collect();
+            if pkgs.is_empty() {
+                fail(*format, "syntax: plan-remove <pkg> [<pkg>...]");
+                return false
+            }
+            show(*format, state.plan_remove(&pkgs));
+        }
+        "distcheck" => {
+            let syntax = "syntax: distcheck [--threads <n>] [--report <file>]";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--threads", "--report"], &[]) {
+                Ok(args) if args.positional.is_empty() => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let threads = match args.flags.get("--threads") {
+                Some(Some(n)) => match n.parse::<usize>() {
+                    Ok(n) if n > 0 => n,
+                    _ => { fail(*format, "--threads needs a positive number"); return false }
+                },
+                _ => std::thread::available_parallelism().map_or(1, |n| n.get())
+            };
+            let report = state.distcheck(threads);
+            match args.flags.get("--report") {
+                Some(Some(fname)) => {
+                    match std::fs::File::create(fname).and_then(|mut f| report.render_as(*format, &mut f)) {
+                        Ok(()) => show(*format, Ok(report.written_to(fname))),
+                        Err(e) => fail(*format, format!("couldn't write {}: {}", fname, e))
+                    }
+                }
+                _ => show(*format, Ok(report))
+            }
+        }
+        "closure-sizes" => {
+            let syntax = "syntax: closure-sizes [--top <n> | --all]";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--top"], &["--all"]) {
+                Ok(args) if args.positional.is_empty() => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let top = match (args.flags.get("--top"), args.flags.contains_key("--all")) {
+                (Some(_), true) => { fail(*format, syntax); return false }
+                (Some(Some(n)), false) => match n.parse::<usize>() {
+                    Ok(n) => Some(n),
+                    Err(_) => { fail(*format, "--top needs a number"); return false }
+                },
+                (_, all) => if all { None } else { Some(20) }
+            };
+            show(*format, Ok(state.closure_sizes(top)));
+        }
+        "weigh" => {
+            let syntax = format!("syntax: weigh <pkg> [--top <n> | --all] [--policy {}]", POLICY_NAMES.join("|"));
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--top", "--policy"], &["--all"]) {
+                Ok(args) if args.positional.len() == 1 => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let top = match (args.flags.get("--top"), args.flags.contains_key("--all")) {
+                (Some(_), true) => { fail(*format, syntax); return false }
+                (Some(Some(n)), false) => match n.parse::<usize>() {
+                    Ok(n) => Some(n),
+                    Err(_) => { fail(*format, "--top needs a number"); return false }
+                },
+                (_, all) => if all { None } else { Some(20) }
+            };
+            let policy = match policy_arg(&args, "first-listed") {
+                Ok(policy) => policy,
+                Err(e) => { fail(*format, e); return false }
+            };
+            show(*format, state.weigh(args.positional[0], policy.as_ref(), top));
+        }
+        "top-depended" => {
+            let syntax = "syntax: top-depended [--installed] [--n <n>]";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--n"], &["--installed"]) {
+                Ok(args) if args.positional.is_empty() => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let n = match args.flags.get("--n").copied().flatten().map(|n| n.parse::<usize>()) {
+                None => 50,
+                Some(Ok(n)) => n,
+                Some(Err(_)) => { fail(*format, "--n needs a number"); return false }
+            };
+            show(*format, Ok(state.top_depended(args.flags.contains_key("--installed"), n)));
+        }
+        "cycles" => {
+            let syntax = "syntax: cycles [--installed] [--from <pkg>]";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--from"], &["--installed"]) {
+                Ok(args) if args.positional.is_empty() => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            show(*format, state.cycles(args.flags.contains_key("--installed"), args.flags.get("--from").copied().flatten()));
+        }
+        "plan-upgrade" | "plan-dist-upgrade" => {
+            if !check_syntax(*format, 1, &cmd_fragments, "") { return false; }
+            show(*format, Ok(state.plan_upgrade(cmd == "plan-dist-upgrade")));
+        }
+        "list-upgradable" => {
+            let syntax = "syntax: list-upgradable [--sort name|section|kind] [--section <section>[,<section>...]]";
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--sort", "--section"], &[]) {
+                Ok(args) if args.positional.is_empty() => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let mut options = UpgradableOptions::default();
+            if let Some(Some(sort)) = args.flags.get("--sort") {
+                match sort.parse() {
+                    Ok(sort) => options.sort = sort,
+                    Err(e) => { fail(*format, e); return false }
+                }
+            }
+            if let Some(Some(sections)) = args.flags.get("--section") {
+                options.sections = sections.split(',').map(String::from).collect();
+            }
+            show(*format, Ok(state.list_upgradable(&options)));
+        }
+
+        "set" => {
+            if !check_syntax(*format, 3, &cmd_fragments, "format <text|json>") { return false; }
+            let (key, value) = (cmd_fragments[1], cmd_fragments[2]);
+            if key != "format" {
+                fail(*format, format!("unknown setting {}", key));
+                return false
+            }
+            match value.parse::<OutputFormat>() {
+                Ok(f) => *format = f,
+                Err(e) => fail(*format, e)
+            }
+        }
+        "build-deps" => {
+            let syntax = format!("syntax: build-deps <srcpkg> [--arch <arch>] [--arch-only | --indep-only] [--policy {}]", POLICY_NAMES.join("|"));
+            let args = match parse_cmd_args(&cmd_fragments[1..], &["--arch", "--policy"], &["--arch-only", "--indep-only"]) {
+                Ok(args) if args.positional.len() == 1 => args,
+                Ok(_) => { fail(*format, syntax); return false }
+                Err(e) => { fail(*format, format!("{}\n{}", e, syntax)); return false }
+            };
+            let (arch_only, indep_only) = (args.flags.contains_key("--arch-only"), args.flags.contains_key("--indep-only"));
+            if arch_only && indep_only {
+                fail(*format, syntax);
+                return false;
+            }
+            let policy = match policy_arg(&args, "first-listed") {
+                Ok(policy) => policy,
+                Err(e) => { fail(*format, e); return false }
+            };
+            let arch = args.flags.get("--arch").copied().flatten().unwrap_or("amd64");
+            show(*format, state.build_deps(args.positional[0], arch, !indep_only, !arch_only, policy.as_ref()));
+        }
+        "solve-install" => {
+            if !check_syntax(*format, 2, &cmd_fragments, "<pkg>") { return false; }
+            let pkg = cmd_fragments.get(1).unwrap();
+            show(*format, state.solve_install(pkg));
         }
 
         "set-server" => {
-            if !check_syntax(2, &cmd_fragments, "<server>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<server>") { return false; }
             let server = cmd_fragments.get(1).unwrap();
             state.set_server(server)
         }
         "execute" => {
-            state.execute();
+            show::<VerificationReport>(*format, Ok(state.execute()));
         }
         "enq-verify" => {
-            let cmd : &str = &cmd_fragments.get(0).unwrap();
+            let cmd : &str = cmd_fragments.first().unwrap();
             if cmd_fragments.len() < 2 || cmd_fragments.len() > 3 {
-                println!("syntax: {} <pkg> [<version>]", cmd);
+                fail(*format, format!("syntax: {} <pkg> [<version>]", cmd));
                 return false
             }
             let pkg = cmd_fragments.get(1).unwrap();
-            if cmd_fragments.len() == 2 {
-                state.enq_verify(pkg);
-            } else {
-                let version = cmd_fragments.get(2).unwrap();
-                state.enq_verify_with_version(pkg, version);
-            }
+            let version = match cmd_fragments.get(2) {
+                Some(version) => version.to_string(),
+                None => {
+                    if !state.package_exists(pkg) {
+                        show_error(*format, &state.no_such_package(pkg));
+                        return false
+                    }
+                    match state.get_available_debver(pkg) {
+                        Some(version) => version.to_string(),
+                        None => { fail(*format, format!("package {} has no available version", pkg)); return false }
+                    }
+                }
+            };
+            let url = state.enq_verify_with_version(pkg, &version);
+            show(*format, Ok(QueuedRequest { package : pkg.to_string(), version, url }));
         }
 
         "output-md5s" => {
-            if !check_syntax(2, &cmd_fragments, "<output-file>") { return false; }
+            if !check_syntax(*format, 2, &cmd_fragments, "<output-file>") { return false; }
             let fname = cmd_fragments.get(1).unwrap();
-            state.output_md5s(fname);
+            if let Err(e) = state.output_md5s(fname) {
+                fail(*format, format!("couldn't write {}: {}", fname, e));
+            }
         }
         "test-version-compare" => {
-            if !check_syntax(3, &cmd_fragments, "<version1> <version2>") { return false; }
+            if !check_syntax(*format, 3, &cmd_fragments, "<version1> <version2>") { return false; }
             let v1 = cmd_fragments.get(1).unwrap().parse::<debversion::DebianVersionNum>().unwrap();
             let v2 = cmd_fragments.get(2).unwrap().parse::<debversion::DebianVersionNum>().unwrap();
             println!("{} and {}: {:?}", v1, v2, v1.cmp(&v2));
@@ -120,24 +479,51 @@ fn process_command(state: &mut Packages, cmdline: &str) -> bool {
             // a vs ~a
         }
         _ => {
-            println!("couldn't understand cmd {:?}", cmd)
+            fail(*format, format!("couldn't understand cmd {:?}", cmd))
         }
     }
     return false;
 }
 
+// the only command-line option is --format <text|json> (or --format=<text|json>)
+fn parse_args() -> Result<OutputFormat, String> {
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    let mut format = OutputFormat::Text;
+    let mut i = 0;
+    while i < args.len() {
+        let value = if args[i] == "--format" {
+            i += 1;
+            args.get(i).ok_or("--format needs an argument")?.as_str()
+        } else if let Some(v) = args[i].strip_prefix("--format=") {
+            v
+        } else {
+            return Err(format!("unknown argument {}", args[i]));
+        };
+        format = value.parse::<OutputFormat>()?;
+        i += 1;
+    }
+    return Ok(format);
+}
+
 fn main() {
     let mut state : Packages = Packages::new();
+    let mut format = match parse_args() {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{}\nusage: rpkg [--format text|json]", e);
+            std::process::exit(2);
+        }
+    };
 
     // bonus (0 points): implement command completion!
     let mut rl = Editor::<()>::new();
-    if rl.load_history("history.txt").is_err() {}
+    let _ = rl.load_history("history.txt");
     loop {
         let readline = rl.readline("$ ");
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
-                if process_command(&mut state, &line) { break }
+                if process_command(&mut state, &mut format, &line) { break }
             },
             Err(ReadlineError::Interrupted) => {
                 break
@@ -152,4 +538,9 @@ fn main() {
         }
     }
     rl.save_history("history.txt").unwrap();
+    // like quit, leaving the REPL any other way still runs the queued verification requests
+    let report = state.execute();
+    if !report.results.is_empty() {
+        show::<VerificationReport>(format, Ok(report));
+    }
 }
@@ -1,13 +1,13 @@
-use urlencoding::encode;
-
 use curl::easy::{Easy2, Handler, WriteError};
 use curl::multi::{Easy2Handle, Multi};
-use std::collections::HashMap;
+use std::io::{self, Write};
 use std::str;
-use std::sync::atomic::{AtomicI32, Ordering};
 use std::time::Duration;
 
+use serde::Serialize;
+
 use crate::Packages;
+use crate::packages::Render;
 
 struct Request {
     package_name: String,
@@ -15,23 +15,15 @@ struct Request {
     url: String,
 }
 
-struct Collector(Box<String>);
+struct Collector(String);
 impl Handler for Collector {
     fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
-        (*self.0).push_str(str::from_utf8(&data.to_vec()).unwrap());
+        self.0.push_str(str::from_utf8(data).unwrap());
         Ok(data.len())
     }
 }
 
 const DEFAULT_SERVER: &str = "ece459.patricklam.ca:4590";
-impl Drop for Packages {
-    fn drop(&mut self) {
-        self.execute()
-    }
-}
-
-static EASYKEY_COUNTER: AtomicI32 = AtomicI32::new(0);
-
 pub struct AsyncState {
     server: String,
     request_queue: Vec<Request>,
@@ -47,49 +39,44 @@ impl AsyncState {
 }
 
 impl Packages {
+    /// Sets the host:port of the checksum server used by subsequent enq_verify calls.
     pub fn set_server(&mut self, new_server: &str) {
         self.async_state.server = String::from(new_server);
     }
 
     /// Retrieves the version number of pkg and calls enq_verify_with_version with that version number.
-    pub fn enq_verify(&mut self, pkg: &str) {
-        let version = self.get_available_debver(pkg);
-        match version {
-            None => {
-                println!("Error: package {} not defined.", pkg);
-                return;
-            }
-            Some(v) => {
-                let vs = &v.to_string();
-                self.enq_verify_with_version(pkg, vs);
-            }
-        };
+    /// Returns the queued URL, or None if pkg has no available version.
+    pub fn enq_verify(&mut self, pkg: &str) -> Option<String> {
+        let version = self.get_available_debver(pkg)?.to_string();
+        return Some(self.enq_verify_with_version(pkg, &version));
     }
 
-    /// Enqueues a request for the provided version/package information. Stores any needed state to async_state so that execute() can handle the results and print out needed output.
-    pub fn enq_verify_with_version(&mut self, pkg: &str, version: &str) {
+    /// Enqueues a request for the provided version/package information. Stores any needed state to async_state so that execute() can handle the results.
+    /// Returns the queued URL.
+    pub fn enq_verify_with_version(&mut self, pkg: &str, version: &str) -> String {
         let url = format!(
             "http://{}/rest/v1/checksums/{}/{}",
             self.async_state.server, pkg, version
         );
 
-        println!("queueing request {}", url);
         self.async_state.request_queue.push(Request {
             package_name: pkg.to_string(),
             version: version.to_string(),
             url: url.to_string(),
         });
+        return url;
     }
 
-    /// Asks curl to perform all enqueued requests. For requests that succeed with response code 200, compares received MD5sum with local MD5sum (perhaps stored earlier). For requests that fail with 400+, prints error message.
-    pub fn execute(&mut self) {
+    /// Asks curl to perform all enqueued requests and empties the queue. For requests that succeed with response code 200, compares received MD5sum with local MD5sum (perhaps stored earlier). For requests that fail with 400+, reports the error code.
+    pub fn execute(&mut self) -> VerificationReport {
         let mut multi = Multi::new();
         let mut easy_req: Vec<Easy2Handle<Collector>> = Vec::new();
+        let requests: Vec<Request> = self.async_state.request_queue.drain(..).collect();
 
         multi.pipelining(true, true).unwrap();
 
-        for request in &self.async_state.request_queue {
-            let mut curr_easy = Easy2::new(Collector(Box::new(String::new())));
+        for request in &requests {
+            let mut curr_easy = Easy2::new(Collector(String::new()));
             curr_easy.url(&request.url).unwrap();
             curr_easy.verbose(false).unwrap();
             easy_req.push(multi.add2(curr_easy).unwrap());
@@ -99,26 +86,77 @@ impl Packages {
             multi.wait(&mut [], Duration::from_secs(10)).unwrap();
         }
 
-        for (index, handle) in easy_req.drain(..).enumerate() {
-            let mut handle = multi.remove2(handle).unwrap();
+        let mut results = vec![];
+        for (request, handle) in requests.into_iter().zip(easy_req.drain(..)) {
+            let handle = multi.remove2(handle).unwrap();
             let res_code = handle.response_code().unwrap();
-            let res = handle.get_ref().0.to_string();
-            let request = &self.async_state.request_queue[index];
-
-            if res_code == 200 {
-                let md5sum = self
-                    .md5sums
-                    .get(&self.package_name_to_num[&request.package_name])
-                    .unwrap();
-                let matches = md5sum == &res;
+            let res = &handle.get_ref().0;
 
-                println!("verifying {}: matches: {:?}", request.package_name, matches);
+            let outcome = if res_code == 200 {
+                match self.get_md5sum(&request.package_name) {
+                    Some(md5sum) => VerificationOutcome::Verified { matches: md5sum == res },
+                    None => VerificationOutcome::NoLocalMd5sum
+                }
             } else {
-                println!(
-                    "got error {} on request for package {} version {}",
-                    res_code, request.package_name, request.version
-                );
+                VerificationOutcome::HttpError { code: res_code }
+            };
+            results.push(VerificationResult { package: request.package_name, version: request.version, outcome });
+        }
+        return VerificationReport { results };
+    }
+}
+
+/// Result of enq-verify: the request that execute() will send.
+#[derive(Serialize)]
+pub struct QueuedRequest {
+    pub package : String,
+    pub version : String,
+    pub url : String,
+}
+
+impl Render for QueuedRequest {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return writeln!(out, "queueing request {}", self.url);
+    }
+}
+
+/// Result of executing the queued verification requests, in the order they were queued.
+#[derive(Serialize)]
+pub struct VerificationReport {
+    pub results : Vec<VerificationResult>,
+}
+
+#[derive(Serialize)]
+pub struct VerificationResult {
+    pub package : String,
+    pub version : String,
+    #[serde(flatten)]
+    pub outcome : VerificationOutcome,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum VerificationOutcome {
+    /// the server answered; matches is true if its md5sum equals the local one
+    Verified { matches : bool },
+    /// the server answered, but we have no local md5sum to compare against
+    NoLocalMd5sum,
+    /// the server answered with this (non-200) response code
+    HttpError { code : u32 },
+}
+
+impl Render for VerificationReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        for r in &self.results {
+            match r.outcome {
+                VerificationOutcome::Verified { matches } =>
+                    writeln!(out, "verifying {}: matches: {:?}", r.package, matches)?,
+                VerificationOutcome::NoLocalMd5sum =>
+                    writeln!(out, "verifying {}: no local md5sum to compare against", r.package)?,
+                VerificationOutcome::HttpError { code } =>
+                    writeln!(out, "got error {} on request for package {} version {}", code, r.package, r.version)?
             }
         }
+        return Ok(());
     }
 }
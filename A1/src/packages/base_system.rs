@@ -0,0 +1,71 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{AlternativePolicy, InstallOrder, Render, SizeSummary};
+use crate::packages::solvers::Planner;
+
+impl Packages {
+    /// Computes a minimal base system the way debootstrap does: the available packages that are Essential: yes or have
+    /// Priority: required (or important, if with_important), closed over their Depends and Pre-Depends on an empty
+    /// system (with compute_install_plan's greedy choices, policy ranking alternatives), in installation order.
+    /// Packages that the closure needs but that aren't available are reported as unsatisfiable.
+    pub fn base_system(&self, with_important: bool, policy: &dyn AlternativePolicy) -> BaseSystem {
+        let priorities : Vec<&str> = if with_important { vec!["required", "important"] } else { vec!["required"] };
+        let mut seeds : Vec<i32> = self.available_debvers.keys().copied()
+            .filter(|p| self.essential.contains(p) || self.priorities.get(p).is_some_and(|prio| priorities.contains(&&prio[..])))
+            .collect();
+        seeds.sort_by_key(|p| self.get_package_name(*p));
+
+        let mut planner = Planner::on_empty_system(self, policy);
+        for p in &seeds {
+            planner.select(*p, None);
+        }
+        planner.run();
+
+        let plan = planner.plan();
+        let install : Vec<i32> = plan.install.iter().copied().filter(|p| self.available_debvers.contains_key(p)).collect();
+        let name = |p: &i32| self.get_package_name(*p).to_string();
+        return BaseSystem {
+            priorities : priorities.iter().map(|prio| prio.to_string()).collect(),
+            seeds : seeds.iter().map(name).collect(),
+            dependencies : install.iter().filter(|p| !seeds.contains(p)).map(name).collect(),
+            unsatisfiable : self.plan_problems(plan),
+            order : self.order_installation("the base system", &install),
+            sizes : self.size_summary(&install, &[], true),
+        };
+    }
+}
+
+/// Result of the base-system query: the packages of a minimal system, debootstrap-style, and how to install them.
+#[derive(Serialize)]
+pub struct BaseSystem {
+    /// the priorities whose packages are included, besides the Essential ones
+    pub priorities : Vec<String>,
+    /// the Essential packages and those with one of priorities
+    pub seeds : Vec<String>,
+    /// the packages their dependencies pull in
+    pub dependencies : Vec<String>,
+    /// dependencies that can't be satisfied, with the reason
+    pub unsatisfiable : Vec<String>,
+    pub order : InstallOrder,
+    /// of installing all of it
+    pub sizes : SizeSummary,
+}
+
+impl Render for BaseSystem {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Base system: {} packages ({} Essential or of priority {}, {} dependencies)",
+            self.seeds.len() + self.dependencies.len(), self.seeds.len(), self.priorities.join(" or "), self.dependencies.len())?;
+        writeln!(out, "selected: {}", self.seeds.join(", "))?;
+        if !self.dependencies.is_empty() {
+            writeln!(out, "dependencies: {}", self.dependencies.join(", "))?;
+        }
+        for u in &self.unsatisfiable {
+            writeln!(out, "can't satisfy: {}", u)?;
+        }
+        self.order.render(out)?;
+        return self.sizes.render(out);
+    }
+}
@@ -0,0 +1,269 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::Render;
+
+/// A set of package numbers, one bit per package; package numbers are dense, so this is compact.
+#[derive(Clone)]
+pub struct BitSet {
+    words : Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> BitSet {
+        return BitSet { words : vec![0; len.div_ceil(64)] };
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        return self.words[i / 64] & (1 << (i % 64)) != 0;
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        for (w, o) in self.words.iter_mut().zip(&other.words) {
+            *w |= o;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.words.iter().map(|w| w.count_ones() as usize).sum();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        return self.words.iter().enumerate()
+            .flat_map(|(i, w)| (0..64).filter(move |b| w & (1 << b) != 0).map(move |b| i * 64 + b));
+    }
+}
+
+/// Tarjan's algorithm, without recursion (dependency chains can be long). Returns the component of each node and the
+/// components' members; the components come in reverse topological order: a component's successors come before it.
+pub fn strongly_connected(edges: &[Vec<usize>]) -> (Vec<usize>, Vec<Vec<usize>>) {
+    const UNVISITED : usize = usize::MAX;
+    let n = edges.len();
+    let mut index = vec![UNVISITED; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut component_of = vec![0; n];
+    let mut components = vec![];
+    let mut counter = 0;
+    for start in 0..n {
+        if index[start] != UNVISITED { continue; }
+        // (node, index of the next edge to follow)
+        let mut calls = vec![(start, 0)];
+        index[start] = counter;
+        lowlink[start] = counter;
+        counter += 1;
+        stack.push(start);
+        on_stack[start] = true;
+        while let Some((v, next)) = calls.last_mut() {
+            let v = *v;
+            if let Some(&w) = edges[v].get(*next) {
+                *next += 1;
+                if index[w] == UNVISITED {
+                    index[w] = counter;
+                    lowlink[w] = counter;
+                    counter += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some((u, _)) = calls.last() {
+                lowlink[*u] = lowlink[*u].min(lowlink[v]);
+            }
+            if lowlink[v] == index[v] {
+                let mut members = vec![];
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component_of[w] = components.len();
+                    members.push(w);
+                    if w == v { break; }
+                }
+                components.push(members);
+            }
+        }
+    }
+    return (component_of, components);
+}
+
+/// Computes transitive_dep_solution's closures (along the first alternative of each clause) in bulk. The dependency
+/// graph is condensed into its strongly connected components once; the packages of a component share one closure,
+/// which is the union of the closures of the components it depends on, and is computed once and remembered.
+pub struct ClosureEngine {
+    component_of : Vec<usize>,
+    components : Vec<Vec<usize>>,
+    // the other components that a component depends on
+    successors : Vec<Vec<usize>>,
+    // whether a component's packages depend on themselves, and are so in their own closures
+    cyclic : Vec<bool>,
+    // a component's members and everything they depend on, once computed
+    reach : Vec<Option<BitSet>>,
+}
+
+impl ClosureEngine {
+    /// An engine over any graph on the nodes 0..edges.len(), given by each node's successors, e.g. the reverse
+    /// dependency graph; Packages::closure_engine() builds the one over the dependency graph.
+    pub fn new(edges: &[Vec<usize>]) -> ClosureEngine {
+        let (component_of, components) = strongly_connected(edges);
+        let mut successors = vec![vec![]; components.len()];
+        let mut cyclic = vec![false; components.len()];
+        for (v, targets) in edges.iter().enumerate() {
+            let c = component_of[v];
+            for &w in targets {
+                if component_of[w] == c {
+                    cyclic[c] = true;
+                } else {
+                    successors[c].push(component_of[w]);
+                }
+            }
+        }
+        for s in successors.iter_mut() {
+            s.sort();
+            s.dedup();
+        }
+        let reach = vec![None; components.len()];
+        return ClosureEngine { component_of, components, successors, cyclic, reach };
+    }
+
+    fn universe(&self) -> usize {
+        return self.component_of.len();
+    }
+
+    // the reach of component c, given that reach has the reach of its successors
+    fn union_of_successors(&self, c: usize, reach: &[Option<BitSet>]) -> BitSet {
+        let mut set = BitSet::new(self.universe());
+        for &m in &self.components[c] {
+            set.insert(m);
+        }
+        for &s in &self.successors[c] {
+            set.union_with(reach[s].as_ref().unwrap());
+        }
+        return set;
+    }
+
+    fn compute_reach(&mut self, c: usize) -> &BitSet {
+        // the components below c that aren't known yet; successors come first in Tarjan's order
+        let mut missing = vec![];
+        let mut visited = BitSet::new(self.components.len());
+        let mut worklist = vec![c];
+        while let Some(d) = worklist.pop() {
+            if self.reach[d].is_some() || visited.contains(d) { continue; }
+            visited.insert(d);
+            missing.push(d);
+            worklist.extend(&self.successors[d]);
+        }
+        missing.sort();
+        for d in missing {
+            let set = self.union_of_successors(d, &self.reach);
+            self.reach[d] = Some(set);
+        }
+        return self.reach[c].as_ref().unwrap();
+    }
+
+    /// The packages in package_num's closure, in package number order. Like transitive_dep_solution, it contains
+    /// package_num itself only if package_num is on a dependency cycle.
+    pub fn closure(&mut self, package_num: i32) -> Vec<i32> {
+        let c = self.component_of[package_num as usize];
+        let cyclic = self.cyclic[c];
+        return self.compute_reach(c).iter()
+            .filter(|p| cyclic || *p != package_num as usize)
+            .map(|p| p as i32)
+            .collect();
+    }
+
+    /// The size of the closure of every package, indexed by package number. Unlike repeated calls to closure(), this
+    /// only keeps each component's closure until everything that depends on it has been computed.
+    pub fn closure_sizes(&self) -> Vec<usize> {
+        let mut predecessors_left = vec![0; self.components.len()];
+        for s in self.successors.iter().flatten() {
+            predecessors_left[*s] += 1;
+        }
+        let mut reach : Vec<Option<BitSet>> = vec![None; self.components.len()];
+        let mut sizes = vec![0; self.universe()];
+        for c in 0..self.components.len() {
+            let set = self.union_of_successors(c, &reach);
+            let size = if self.cyclic[c] { set.len() } else { set.len() - 1 };
+            for &m in &self.components[c] {
+                sizes[m] = size;
+            }
+            for &s in &self.successors[c] {
+                predecessors_left[s] -= 1;
+                if predecessors_left[s] == 0 {
+                    reach[s] = None;
+                }
+            }
+            if predecessors_left[c] > 0 {
+                reach[c] = Some(set);
+            }
+        }
+        return sizes;
+    }
+}
+
+impl Packages {
+    /// A ClosureEngine over the dependency graph of this database, for the closures of transitive_dep_solution
+    /// (see FirstListed).
+    pub fn closure_engine(&self) -> ClosureEngine {
+        let edges : Vec<Vec<usize>> = (0..self.package_num_to_name.len() as i32).map(|p| {
+            let mut targets : Vec<usize> = self.dependencies[&p].iter()
+                .filter_map(|dep| dep.first())
+                .map(|a| a.package_num as usize)
+                .collect();
+            targets.sort();
+            targets.dedup();
+            targets
+        }).collect();
+        return ClosureEngine::new(&edges);
+    }
+
+    /// Returns the size of the transitive dependency solution of every package in the Packages index, largest first
+    /// (ties by name); only the first top if top is Some.
+    pub fn closure_sizes(&self, top: Option<usize>) -> ClosureSizeReport {
+        let sizes = self.closure_engine().closure_sizes();
+        let mut largest : Vec<ClosureSize> = self.available_debvers.keys()
+            .map(|p| ClosureSize { package : self.get_package_name(*p).to_string(), size : sizes[*p as usize] })
+            .collect();
+        largest.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.package.cmp(&b.package)));
+        let packages = largest.len();
+        if let Some(top) = top {
+            largest.truncate(top);
+        }
+        return ClosureSizeReport { packages, largest };
+    }
+}
+
+/// Result of closure-sizes: how many packages the transitive dependency solution of each available package has.
+#[derive(Serialize)]
+pub struct ClosureSizeReport {
+    /// the number of available packages
+    pub packages : usize,
+    /// largest first, ties by name; possibly only the first few
+    pub largest : Vec<ClosureSize>,
+}
+
+#[derive(Serialize)]
+pub struct ClosureSize {
+    pub package : String,
+    pub size : usize,
+}
+
+impl Render for ClosureSizeReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "closure sizes of {} packages, largest first:", self.packages)?;
+        for c in &self.largest {
+            writeln!(out, "{:>7} {}", c.size, c.package)?;
+        }
+        return Ok(());
+    }
+}
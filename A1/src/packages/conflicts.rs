@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Packages;
+use crate::debversion::DebianVersionNum;
+use crate::packages::{Conflict, ConflictKind};
+use crate::packages::deps_available::version_satisfies;
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            ConflictKind::Conflicts => write!(f, "conflicts with"),
+            ConflictKind::Breaks => write!(f, "breaks")
+        }
+    }
+}
+
+/// Points at one Conflicts/Breaks entry: the package that declares it, and the entry's index in its list.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ConflictRef {
+    pub declared_by : i32,
+    pub index : usize,
+}
+
+/// Reverse index of the Conflicts/Breaks fields: package number -> packages that declare a conflict with it.
+pub type ConflictIndex = HashMap<i32, Vec<i32>>;
+
+impl Packages {
+    /// Returns the Conflicts and Breaks of package_num at version. The Packages index only tells us those of the
+    /// available version, so any other version has none.
+    pub fn declared_conflicts(&self, package_num: i32, version: &DebianVersionNum) -> &[Conflict] {
+        if self.available_debvers.get(&package_num) != Some(version) {
+            return &[];
+        }
+        return self.conflicts.get(&package_num).map_or(&[], |c| &c[..]);
+    }
+
+    /// Builds the reverse index of all Conflicts/Breaks fields; conflicts_between needs it to find the conflicts
+    /// that other packages declare against a package.
+    pub fn conflict_index(&self) -> ConflictIndex {
+        let mut index : ConflictIndex = HashMap::new();
+        for (package_num, conflicts) in &self.conflicts {
+            for c in conflicts {
+                let declarers = index.entry(c.target.package_num).or_default();
+                if !declarers.contains(package_num) {
+                    declarers.push(*package_num);
+                }
+            }
+        }
+        return index;
+    }
+
+    /// Finds the conflicts, in either direction, between package_num at version and the packages that version_of
+    /// returns a version for (e.g. the installed packages). Returns the other package of each conflict.
+    /// A package never conflicts with itself.
+    pub fn conflicts_between<'a>(&self, index: &ConflictIndex, package_num: i32, version: &DebianVersionNum,
+                                 version_of: impl Fn(i32) -> Option<&'a DebianVersionNum>) -> Vec<(i32, ConflictRef)> {
+        let mut result = vec![];
+        for (i, c) in self.declared_conflicts(package_num, version).iter().enumerate() {
+            let other = c.target.package_num;
+            if other == package_num { continue; }
+            if version_of(other).is_some_and(|v| version_satisfies(v, &c.target.rel_version)) {
+                result.push((other, ConflictRef { declared_by : package_num, index : i }));
+            }
+        }
+        for other in index.get(&package_num).map_or(&[][..], |d| &d[..]) {
+            if *other == package_num { continue; }
+            let other_version = match version_of(*other) {
+                None => continue,
+                Some(v) => v
+            };
+            for (i, c) in self.declared_conflicts(*other, other_version).iter().enumerate() {
+                if c.target.package_num == package_num && version_satisfies(version, &c.target.rel_version) {
+                    result.push((*other, ConflictRef { declared_by : *other, index : i }));
+                }
+            }
+        }
+        return result;
+    }
+
+    /// Formats a conflict, e.g. "gawk conflicts with mawk" or "libc6 breaks hurd (<< 1:0.9.git20170910-1)".
+    pub fn conflict2str(&self, c: ConflictRef) -> String {
+        let conflict = &self.conflicts[&c.declared_by][c.index];
+        let target = self.get_package_name(conflict.target.package_num);
+        return match &conflict.target.rel_version {
+            None => format!("{} {} {}", self.get_package_name(c.declared_by), conflict.kind, target),
+            Some((rel, ver)) => format!("{} {} {} ({} {})", self.get_package_name(c.declared_by), conflict.kind, target, rel, ver)
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{Dependency, NoSuchPackage, Render};
+use crate::packages::closure::strongly_connected;
+
+// an edge from a package to an alternative of one of its Depends (or Pre-Depends) clauses
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct DepEdge {
+    to : usize,
+    pre_depends : bool,
+    clause : usize,
+}
+
+impl Packages {
+    /// Finds the dependency cycles among the available packages (or the installed ones if installed): the strongly
+    /// connected components of the graph with an edge from each package to every alternative of its Depends and
+    /// Pre-Depends clauses that is among them. Installed packages have the dependencies of the Packages index. If from
+    /// is Some, only the packages that it reaches in that graph count. Each cycle comes with the edges within it, and
+    /// the ones among them that close it: without those, what is left of the cycle is acyclic.
+    pub fn cycles(&self, installed: bool, from: Option<&str>) -> Result<CycleReport, NoSuchPackage> {
+        let in_set = |p: i32| if installed { self.installed_debvers.contains_key(&p) } else { self.available_debvers.contains_key(&p) };
+        let n = self.package_num_to_name.len();
+        let mut edges : Vec<Vec<DepEdge>> = vec![vec![]; n];
+        for (p, out) in edges.iter_mut().enumerate() {
+            let p = p as i32;
+            if !in_set(p) { continue; }
+            let relations = [(true, self.pre_dependencies.get(&p)), (false, self.dependencies.get(&p))];
+            for &(pre_depends, deps) in relations.iter() {
+                for (clause, dep) in deps.map_or(&[][..], |d| &d[..]).iter().enumerate() {
+                    for a in dep {
+                        let e = DepEdge { to : a.package_num as usize, pre_depends, clause };
+                        if in_set(a.package_num) && !out.contains(&e) {
+                            out.push(e);
+                        }
+                    }
+                }
+            }
+        }
+
+        let set_name = if installed { "installed packages" } else { "available packages" };
+        let mut scope = set_name.to_string();
+        let mut considered : usize = (0..n as i32).filter(|p| in_set(*p)).count();
+        if let Some(name) = from {
+            let root = self.find_package_num(name).ok_or_else(|| self.no_such_package(name))?;
+            let reached = reachable(&edges, root as usize);
+            for (p, out) in edges.iter_mut().enumerate() {
+                if !reached.contains(&p) {
+                    out.clear();
+                }
+            }
+            scope = format!("{} reachable from {}", set_name, name);
+            considered = reached.iter().filter(|p| in_set(**p as i32)).count();
+        }
+
+        let adjacency : Vec<Vec<usize>> = edges.iter().map(|out| out.iter().map(|e| e.to).collect()).collect();
+        let (component_of, components) = strongly_connected(&adjacency);
+        let mut cycles = vec![];
+        for (c, members) in components.iter().enumerate() {
+            let within = |p: usize| component_of[p] == c;
+            if members.len() == 1 && !edges[members[0]].iter().any(|e| e.to == members[0]) { continue; }
+            let mut members = members.clone();
+            members.sort_by_key(|p| self.get_package_name(*p as i32));
+            let closing = closing_edges(&edges, &members, within);
+            let mut cycle = DependencyCycle { members : members.iter().map(|p| self.get_package_name(*p as i32).to_string()).collect(), edges : vec![] };
+            for &p in &members {
+                for e in edges[p].iter().filter(|e| within(e.to)) {
+                    cycle.edges.push(self.cycle_edge(p, e, closing.contains(&(p, *e))));
+                }
+            }
+            cycles.push(cycle);
+        }
+        cycles.sort_by(|a, b| b.members.len().cmp(&a.members.len()).then_with(|| a.members.cmp(&b.members)));
+        return Ok(CycleReport { scope, packages : considered, cycles });
+    }
+
+    fn cycle_edge(&self, from: usize, e: &DepEdge, closes_cycle: bool) -> CycleEdge {
+        let deps : &Vec<Dependency> = if e.pre_depends { &self.pre_dependencies[&(from as i32)] } else { &self.dependencies[&(from as i32)] };
+        return CycleEdge {
+            from : self.get_package_name(from as i32).to_string(),
+            to : self.get_package_name(e.to as i32).to_string(),
+            pre_depends : e.pre_depends,
+            dependency : self.dep2str(&deps[e.clause]),
+            closes_cycle,
+        };
+    }
+}
+
+// the nodes that start reaches along edges, itself included
+fn reachable(edges: &[Vec<DepEdge>], start: usize) -> HashSet<usize> {
+    let mut reached = HashSet::new();
+    let mut worklist = VecDeque::new();
+    reached.insert(start);
+    worklist.push_back(start);
+    while let Some(p) = worklist.pop_front() {
+        for e in &edges[p] {
+            if reached.insert(e.to) {
+                worklist.push_back(e.to);
+            }
+        }
+    }
+    return reached;
+}
+
+// the back edges of a depth-first search of a strongly connected component, from its first member: the edges that
+// lead back to a package whose dependencies are still being explored
+fn closing_edges(edges: &[Vec<DepEdge>], members: &[usize], within: impl Fn(usize) -> bool) -> HashSet<(usize, DepEdge)> {
+    let mut closing = HashSet::new();
+    let mut on_path : HashSet<usize> = HashSet::new();
+    let mut done : HashSet<usize> = HashSet::new();
+    // (package, index of the next edge to follow)
+    let mut calls = vec![(members[0], 0)];
+    on_path.insert(members[0]);
+    while let Some((p, next)) = calls.last_mut() {
+        let p = *p;
+        match edges[p].get(*next) {
+            Some(e) => {
+                *next += 1;
+                if !within(e.to) || done.contains(&e.to) { continue; }
+                if on_path.contains(&e.to) {
+                    closing.insert((p, *e));
+                } else {
+                    on_path.insert(e.to);
+                    calls.push((e.to, 0));
+                }
+            }
+            None => {
+                calls.pop();
+                on_path.remove(&p);
+                done.insert(p);
+            }
+        }
+    }
+    return closing;
+}
+
+/// Result of the cycles query: the dependency cycles (strongly connected components) among a set of packages.
+#[derive(Serialize)]
+pub struct CycleReport {
+    /// the set of packages, e.g. "available packages" or "installed packages reachable from bash"
+    pub scope : String,
+    /// how many packages are in it
+    pub packages : usize,
+    /// largest first
+    pub cycles : Vec<DependencyCycle>,
+}
+
+#[derive(Serialize)]
+pub struct DependencyCycle {
+    pub members : Vec<String>,
+    /// the dependencies between members
+    pub edges : Vec<CycleEdge>,
+}
+
+/// A dependency of one member of a cycle on another: an alternative of a Depends or Pre-Depends clause.
+#[derive(Serialize)]
+pub struct CycleEdge {
+    pub from : String,
+    pub to : String,
+    pub pre_depends : bool,
+    /// the clause, formatted as in the Packages file
+    pub dependency : String,
+    /// whether this is one of the edges that close the cycle: without them, the rest of it is acyclic
+    pub closes_cycle : bool,
+}
+
+impl Render for CycleReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.cycles.is_empty() {
+            return writeln!(out, "no dependency cycles among the {} {}", self.packages, self.scope);
+        }
+        let plural = if self.cycles.len() == 1 { "" } else { "s" };
+        writeln!(out, "{} dependency cycle{} among the {} {}:", self.cycles.len(), plural, self.packages, self.scope)?;
+        for cycle in &self.cycles {
+            writeln!(out, "{}", cycle.members.join(", "))?;
+            for e in &cycle.edges {
+                let relation = if e.pre_depends { "pre-depends" } else { "depends" };
+                let closes = if e.closes_cycle { " (closes the cycle)" } else { "" };
+                if e.dependency == e.to {
+                    writeln!(out, "  {} {} on {}{}", e.from, relation, e.to, closes)?;
+                } else {
+                    writeln!(out, "  {} {} on {} via {:?}{}", e.from, relation, e.to, e.dependency, closes)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+}
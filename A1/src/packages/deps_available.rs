@@ -1,53 +1,44 @@
-use rpkg::debversion;
+use std::io::{self, Write};
+
+use crate::debversion;
+use crate::debversion::{DebianVersionNum, VersionRelation};
+use serde::Serialize;
+
 use crate::Packages;
-use crate::packages::Dependency;
+use crate::packages::{Dependency, NoSuchPackage, Render};
 
 impl Packages {
-    /// Gets the dependencies of package_name, and prints out whether they are satisfied (and by which library/version) or not.
-    pub fn deps_available(&self, package_name: &str) {
+    /// Gets the dependencies of package_name, and reports whether they are satisfied (and by which library/version) or not.
+    pub fn deps_available(&self, package_name: &str) -> Result<DepsAvailabilityReport, NoSuchPackage> {
         if !self.package_exists(package_name) {
-            println!("no such package {}", package_name);
-            return;
+            return Err(self.no_such_package(package_name));
         }
 
-        let deps : &Vec<Dependency> = &*self.dependencies.get(self.get_package_num(package_name)).unwrap();
+        let deps : &Vec<Dependency> = self.dependencies.get(self.get_package_num(package_name)).unwrap();
 
-        println!("Package {}:", package_name);
+        let mut dependencies = vec![];
         for dep in deps {
-            println!("- dependency {:?}", self.dep2str(dep));
-            if let Some(pkg) = self.dep_is_satisfied(dep) {
-                let installed_version = self.get_installed_debver(pkg).unwrap();
-                println!("+ {} satisfied by installed version {}", pkg, installed_version);
-            } else {
-                println!("-> not satisfied");
-            }
+            let satisfied_by = self.dep_is_satisfied(dep).map(|pkg| SatisfiedBy {
+                package : pkg.to_string(),
+                installed_version : self.get_installed_debver(pkg).unwrap().to_string(),
+            });
+            dependencies.push(DependencyAvailability { dependency : self.dep2str(dep), satisfied_by });
         }
+        return Ok(DepsAvailabilityReport { package : package_name.to_string(), dependencies });
     }
 
     /// Returns Some(package) which satisfies dependency dd, or None if not satisfied.
     pub fn dep_is_satisfied(&self, dd:&Dependency) -> Option<&str> {
-        // presumably you should loop on dd
+        return self.dep_is_satisfied_in(dd, |p| self.installed_debvers.get(&p)).map(|p| self.get_package_name(p));
+    }
 
+    /// Like dep_is_satisfied, but against a hypothetical set of installed packages: installed(p) is the installed version
+    /// of p, if any. Returns the number of the package which satisfies dd.
+    pub fn dep_is_satisfied_in<'a>(&self, dd: &Dependency, installed: impl Fn(i32) -> Option<&'a DebianVersionNum>) -> Option<i32> {
         for dep in dd {
-            let package_name = self.get_package_name(dep.package_num);
-            let installed_debver = self.get_installed_debver(package_name);
-
-            if installed_debver.is_some() {
-                let installed_version = installed_debver.unwrap();
-
-                if dep.rel_version.is_none() {
-                    return Some(package_name);
-                } else {
-                    let rel_version = dep.rel_version.as_ref().unwrap();
-                    let op = &rel_version.0;
-                    let ver = &rel_version.1;
-                    let ver_debver = ver.parse::<debversion::DebianVersionNum>().unwrap();
-
-                    if debversion::cmp_debversion_with_op(op, installed_version, &ver_debver) {
-                        return Some(package_name);
-                    } else {
-                        continue;
-                    }
+            if let Some(installed_version) = installed(dep.package_num) {
+                if version_satisfies(installed_version, &dep.rel_version) {
+                    return Some(dep.package_num);
                 }
             }
         }
@@ -55,35 +46,96 @@ impl Packages {
         return None;
     }
 
+    /// Checks the Depends and Pre-Depends clauses of package_nums against a hypothetical set of installed packages (see
+    /// dep_is_satisfied_in), and returns the unsatisfied ones as (package, clause index), numbered from the Depends on,
+    /// then the Pre-Depends. A package is only checked if installed(package) is its available version, since that is the
+    /// only version whose dependencies we know.
+    pub fn unsatisfied_deps_in<'a>(&self, package_nums: &[i32], installed: impl Fn(i32) -> Option<&'a DebianVersionNum>) -> Vec<(i32, usize)> {
+        let mut result = vec![];
+        for package_num in package_nums {
+            if installed(*package_num).is_none() || installed(*package_num) != self.available_debvers.get(package_num) {
+                continue;
+            }
+            for (i, dep) in self.install_clauses(*package_num).enumerate() {
+                if self.dep_is_satisfied_in(dep, &installed).is_none() {
+                    result.push((*package_num, i));
+                }
+            }
+        }
+        return result;
+    }
+
     /// Returns a Vec of packages which would satisfy dependency dd but for the version.
     /// Used by the how-to-install command, which calls compute_how_to_install().
     pub fn dep_satisfied_by_wrong_version(&self, dd:&Dependency) -> Vec<&str> {
         assert! (self.dep_is_satisfied(dd).is_none());
-        let mut result = vec![];
-        // another loop on dd
+        return self.dep_satisfied_by_wrong_version_in(dd, |p| self.installed_debvers.get(&p))
+            .into_iter().map(|p| self.get_package_name(p)).collect();
+    }
 
+    /// Like dep_satisfied_by_wrong_version, but against a hypothetical set of installed packages (see dep_is_satisfied_in).
+    pub fn dep_satisfied_by_wrong_version_in<'a>(&self, dd: &Dependency, installed: impl Fn(i32) -> Option<&'a DebianVersionNum>) -> Vec<i32> {
+        let mut result = vec![];
         for dep in dd {
-            let package_name = self.get_package_name(dep.package_num);
-            let installed_debver = self.get_installed_debver(package_name);
-
-            if installed_debver.is_some() {
-                let installed_version = installed_debver.unwrap();
-
-                if dep.rel_version.is_none() {
-                    continue;
-                } else {
-                    let rel_version = dep.rel_version.as_ref().unwrap();
-                    let op = &rel_version.0;
-                    let ver = &rel_version.1;
-                    let ver_debver = ver.parse::<debversion::DebianVersionNum>().unwrap();
-                    if !debversion::cmp_debversion_with_op(op, installed_version, &ver_debver) {
-                        result.push(package_name);
-                    }
+            if let (Some(installed_version), Some(_)) = (installed(dep.package_num), &dep.rel_version) {
+                if !version_satisfies(installed_version, &dep.rel_version) {
+                    result.push(dep.package_num);
                 }
             }
         }
 
         return result;
     }
+
+    /// Returns true if package_num is an alternative of dd whose available version doesn't satisfy it; false if it
+    /// has no available version.
+    pub fn wrong_available_version(&self, dd: &Dependency, package_num: i32) -> bool {
+        return match self.available_debvers.get(&package_num) {
+            None => false,
+            Some(v) => !dd.iter().any(|dep| dep.package_num == package_num && version_satisfies(v, &dep.rel_version))
+        };
+    }
+}
+
+/// Returns true if version satisfies the (optional) version constraint of a dependency alternative.
+pub fn version_satisfies(version: &DebianVersionNum, rel_version: &Option<(VersionRelation, String)>) -> bool {
+    return match rel_version {
+        None => true,
+        Some((op, ver)) => debversion::cmp_debversion_with_op(op, version, &ver.parse::<DebianVersionNum>().unwrap())
+    }
+}
+
+/// Result of the deps-available query, one entry per Depends clause.
+#[derive(Serialize)]
+pub struct DepsAvailabilityReport {
+    pub package : String,
+    pub dependencies : Vec<DependencyAvailability>,
 }
 
+#[derive(Serialize)]
+pub struct DependencyAvailability {
+    /// the clause, formatted as in the Packages file
+    pub dependency : String,
+    /// the installed package satisfying the clause, or None if it is not satisfied
+    pub satisfied_by : Option<SatisfiedBy>,
+}
+
+#[derive(Serialize)]
+pub struct SatisfiedBy {
+    pub package : String,
+    pub installed_version : String,
+}
+
+impl Render for DepsAvailabilityReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Package {}:", self.package)?;
+        for dep in &self.dependencies {
+            writeln!(out, "- dependency {:?}", dep.dependency)?;
+            match &dep.satisfied_by {
+                Some(s) => writeln!(out, "+ {} satisfied by installed version {}", s.package, s.installed_version)?,
+                None => writeln!(out, "-> not satisfied")?
+            }
+        }
+        return Ok(());
+    }
+}
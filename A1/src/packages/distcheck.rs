@@ -0,0 +1,232 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Entry;
+use std::io::{self, Write};
+use std::thread;
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{ConflictIndex, Dependency, Render};
+use crate::packages::deps_available::version_satisfies;
+
+// explaining a package that only the SAT solver finds broken re-solves its problem once per constraint; with the
+// whole archive to get through, only do that for small problems
+const MAX_CORE_CLAUSES : usize = 300;
+
+impl Packages {
+    /// Checks every package in the Packages index for installability against the index itself, like dose-distcheck:
+    /// could its available version be installed on an empty system, using only available versions, with every version
+    /// constraint and Conflicts/Breaks respected? The packages are split between threads threads.
+    pub fn distcheck(&self, threads: usize) -> DistcheckReport {
+        let index = self.conflict_index();
+        let closed = self.archive_closed_set();
+        let mut package_nums : Vec<i32> = self.available_debvers.keys().copied().collect();
+        package_nums.sort_by_key(|p| self.get_package_name(*p));
+        let threads = threads.max(1);
+
+        // thread t checks packages t, t + threads, t + 2 * threads, ..., so that each gets a similar mix
+        let mut results : Vec<Option<BrokenPackage>> = (0..package_nums.len()).map(|_| None).collect();
+        thread::scope(|scope| {
+            let handles : Vec<_> = (0..threads).map(|t| {
+                let (package_nums, index, closed) = (&package_nums, &index, &closed);
+                scope.spawn(move || {
+                    package_nums.iter().enumerate().skip(t).step_by(threads)
+                        .map(|(i, p)| (i, self.check_installable(*p, index, closed)))
+                        .collect::<Vec<_>>()
+                })
+            }).collect();
+            for handle in handles {
+                for (i, result) in handle.join().unwrap() {
+                    results[i] = result;
+                }
+            }
+        });
+
+        let broken : Vec<BrokenPackage> = results.into_iter().flatten().collect();
+        return DistcheckReport { checked : package_nums.len(), installable : package_nums.len() - broken.len(), broken };
+    }
+
+    // The largest set of available packages in which every Depends and Pre-Depends clause of every package has an
+    // alternative whose available version satisfies it and that is in the set. Without Conflicts/Breaks, a package could
+    // be installed from the archive if and only if it is in the set: installing its closure within the set does it.
+    fn archive_closed_set(&self) -> HashSet<i32> {
+        // for each package, the clauses that it satisfies, and for each clause, how many of its alternatives are left
+        let mut satisfies : HashMap<i32, Vec<(i32, usize)>> = HashMap::new();
+        let mut left : HashMap<(i32, usize), usize> = HashMap::new();
+        let mut removed = vec![];
+        for package_num in self.available_debvers.keys() {
+            for (i, dep) in self.install_clauses(*package_num).enumerate() {
+                let mut alternatives : Vec<i32> = self.satisfying_alternatives(dep).collect();
+                alternatives.sort();
+                alternatives.dedup();
+                if alternatives.is_empty() {
+                    removed.push(*package_num);
+                }
+                left.insert((*package_num, i), alternatives.len());
+                for a in alternatives {
+                    satisfies.entry(a).or_default().push((*package_num, i));
+                }
+            }
+        }
+        let mut closed : HashSet<i32> = self.available_debvers.keys().copied().collect();
+        while let Some(r) = removed.pop() {
+            if !closed.remove(&r) { continue; }
+            for clause in satisfies.get(&r).map_or(&[][..], |c| &c[..]) {
+                let n = left.get_mut(clause).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    removed.push(clause.0);
+                }
+            }
+        }
+        return closed;
+    }
+
+    // the alternatives of dep whose available version satisfies it
+    fn satisfying_alternatives<'a>(&'a self, dep: &'a Dependency) -> impl Iterator<Item = i32> + 'a {
+        return dep.iter()
+            .filter(move |a| self.available_debvers.get(&a.package_num).is_some_and(|v| version_satisfies(v, &a.rel_version)))
+            .map(|a| a.package_num);
+    }
+
+    // whether two packages in package_num's closure within closed conflict with each other
+    fn conflicts_in_closure(&self, package_num: i32, index: &ConflictIndex, closed: &HashSet<i32>) -> bool {
+        let mut closure : HashSet<i32> = HashSet::new();
+        let mut worklist = VecDeque::new();
+        closure.insert(package_num);
+        worklist.push_back(package_num);
+        while let Some(p) = worklist.pop_front() {
+            for dep in self.install_clauses(p) {
+                for a in self.satisfying_alternatives(dep) {
+                    if closed.contains(&a) && closure.insert(a) {
+                        worklist.push_back(a);
+                    }
+                }
+            }
+        }
+        let in_closure = |p| if closure.contains(&p) { self.available_debvers.get(&p) } else { None };
+        return closure.iter().any(|p| !self.conflicts_between(index, *p, &self.available_debvers[p], in_closure).is_empty());
+    }
+
+    // None if package_num is installable from the archive, otherwise why not
+    fn check_installable(&self, package_num: i32, index: &ConflictIndex, closed: &HashSet<i32>) -> Option<BrokenPackage> {
+        if closed.contains(&package_num) && !self.conflicts_in_closure(package_num, index, closed) {
+            return None;
+        }
+        let mut broken = BrokenPackage {
+            package : self.get_package_name(package_num).to_string(),
+            version : self.available_debvers[&package_num].to_string(),
+            via : vec![],
+            clause : None,
+            reasons : vec![],
+        };
+        // most broken packages are broken because of a dependency with no way to satisfy it, which is cheap to find
+        if let Some((chain, i)) = self.forced_dead_end(package_num) {
+            let owner = *chain.last().unwrap();
+            let (dep, relation) = self.install_clause(owner, i);
+            broken.via = chain.iter().map(|p| self.get_package_name(*p).to_string()).collect();
+            broken.clause = Some(format!("{} {} {:?}", self.get_package_name(owner), relation, self.dep2str(dep)));
+            broken.reasons = dep.iter().map(|a| match self.available_debvers.get(&a.package_num) {
+                None => format!("{} is not available", self.get_package_name(a.package_num)),
+                Some(v) => format!("{} {} is not {}", self.get_package_name(a.package_num), v,
+                    a.rel_version.as_ref().map_or(String::new(), |(rel, ver)| format!("{} {}", rel, ver)))
+            }).collect();
+            return Some(broken);
+        }
+        broken.reasons = self.installable_in_archive(package_num, index, MAX_CORE_CLAUSES)?;
+        return Some(broken);
+    }
+
+    // follows the Depends and Pre-Depends clauses that only one available package satisfies, from package_num; returns
+    // the first clause that no available package satisfies, as the chain of packages from package_num to its owner and
+    // its index (see install_clause)
+    fn forced_dead_end(&self, package_num: i32) -> Option<(Vec<i32>, usize)> {
+        let mut parent : HashMap<i32, Option<i32>> = HashMap::new();
+        let mut worklist = VecDeque::new();
+        parent.insert(package_num, None);
+        worklist.push_back(package_num);
+        while let Some(p) = worklist.pop_front() {
+            for (i, dep) in self.install_clauses(p).enumerate() {
+                let mut satisfying = self.satisfying_alternatives(dep);
+                match (satisfying.next(), satisfying.next()) {
+                    (None, _) => {
+                        let mut chain = vec![p];
+                        while let Some(Some(q)) = parent.get(chain.last().unwrap()) {
+                            chain.push(*q);
+                        }
+                        chain.reverse();
+                        return Some((chain, i));
+                    }
+                    (Some(only), None) => if let Entry::Vacant(e) = parent.entry(only) {
+                        e.insert(Some(p));
+                        worklist.push_back(only);
+                    },
+                    _ => ()
+                }
+            }
+        }
+        return None;
+    }
+}
+
+/// Result of distcheck: which packages of the Packages index can't be installed from it.
+#[derive(Serialize)]
+pub struct DistcheckReport {
+    pub checked : usize,
+    pub installable : usize,
+    pub broken : Vec<BrokenPackage>,
+}
+
+/// A package that distcheck found uninstallable.
+#[derive(Serialize)]
+pub struct BrokenPackage {
+    pub package : String,
+    pub version : String,
+    /// the packages it necessarily pulls in, from itself to the owner of clause
+    pub via : Vec<String>,
+    /// the dependency that nothing in the archive satisfies; None if the problem is a combination of constraints
+    pub clause : Option<String>,
+    /// why clause's alternatives don't do, or else constraints that can't all hold
+    pub reasons : Vec<String>,
+}
+
+impl DistcheckReport {
+    /// One line with the counts.
+    pub fn summary(&self) -> String {
+        return format!("checked {} packages: {} installable, {} broken", self.checked, self.installable, self.broken.len());
+    }
+
+    /// The counts, for when the report itself is written to the file report.
+    pub fn written_to(&self, report: &str) -> DistcheckSummary {
+        return DistcheckSummary { checked : self.checked, installable : self.installable, broken : self.broken.len(), report : report.to_string() };
+    }
+}
+
+/// What distcheck --report prints: the counts, and the file the report went to.
+#[derive(Serialize)]
+pub struct DistcheckSummary {
+    pub checked : usize,
+    pub installable : usize,
+    pub broken : usize,
+    pub report : String,
+}
+
+impl Render for DistcheckSummary {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return writeln!(out, "checked {} packages: {} installable, {} broken; report written to {}", self.checked, self.installable, self.broken, self.report);
+    }
+}
+
+impl Render for DistcheckReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{}", self.summary())?;
+        for b in &self.broken {
+            match &b.clause {
+                Some(clause) if b.via.len() > 1 => writeln!(out, "{} {}: {} (via {}): {}", b.package, b.version, clause, b.via.join(" -> "), b.reasons.join("; "))?,
+                Some(clause) => writeln!(out, "{} {}: {}: {}", b.package, b.version, clause, b.reasons.join("; "))?,
+                None => writeln!(out, "{} {}: these can't all hold: {}", b.package, b.version, b.reasons.join("; "))?
+            }
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use petgraph::Graph;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{NoSuchPackage, Render};
+
+/// A node of the graph built by dependency_graph.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DepGraphNode {
+    Package(i32),
+    /// a Depends clause (package, index) with alternatives; it has an edge to each alternative
+    Or(i32, usize),
+}
+
+/// Edges go from a package to its dependencies (or to an OR-node), labelled with the version constraint, if any.
+pub type DepGraph = Graph<DepGraphNode, Option<String>>;
+
+/// Whether a package of a dependency graph is installed, only in the Packages index, or in neither.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PackageStatus { Installed, Available, Missing }
+
+impl PackageStatus {
+    /// The status as written in GraphML.
+    pub fn label(self) -> &'static str {
+        return match self {
+            PackageStatus::Installed => "installed",
+            PackageStatus::Available => "available",
+            PackageStatus::Missing => "missing",
+        };
+    }
+
+    /// The Graphviz color name a node with this status is filled with.
+    pub fn dot_color(self) -> &'static str {
+        return match self {
+            PackageStatus::Installed => "palegreen",
+            PackageStatus::Available => "white",
+            PackageStatus::Missing => "lightcoral",
+        };
+    }
+
+    /// dot_color as an RGB hex color, for GraphML.
+    pub fn rgb_color(self) -> &'static str {
+        return match self {
+            PackageStatus::Installed => "#98fb98",
+            PackageStatus::Available => "#ffffff",
+            PackageStatus::Missing => "#f08080",
+        };
+    }
+}
+
+/// File formats of export-graph.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GraphFormat { Dot, GraphMl }
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            _ => Err(format!("unknown graph format {} (expected dot or graphml)", s))
+        }
+    }
+}
+
+/// Result of export-graph: the file the graph of package's dependencies was written to, and its size.
+#[derive(Serialize)]
+pub struct GraphExported {
+    pub package : String,
+    pub file : String,
+    pub nodes : usize,
+    pub edges : usize,
+}
+
+impl Render for GraphExported {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return writeln!(out, "wrote {} nodes and {} edges to {}", self.nodes, self.edges, self.file);
+    }
+}
+
+impl Packages {
+    /// Builds the graph of package_name's Depends, following them up to depth edges away (OR-nodes don't count),
+    /// or all the way if depth is None.
+    pub fn dependency_graph(&self, package_name: &str, depth: Option<usize>) -> Result<DepGraph, NoSuchPackage> {
+        let root = self.find_package_num(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        let mut graph = DepGraph::new();
+        let mut nodes : HashMap<i32, NodeIndex> = HashMap::new();
+        let mut worklist : VecDeque<(i32, usize)> = VecDeque::new();
+        nodes.insert(root, graph.add_node(DepGraphNode::Package(root)));
+        worklist.push_back((root, 0));
+
+        while let Some((package_num, d)) = worklist.pop_front() {
+            if depth.is_some_and(|depth| d >= depth) { continue; }
+            let from = nodes[&package_num];
+            for (i, dep) in self.dependencies[&package_num].iter().enumerate() {
+                let source = if dep.len() > 1 {
+                    let or = graph.add_node(DepGraphNode::Or(package_num, i));
+                    graph.add_edge(from, or, None);
+                    or
+                } else {
+                    from
+                };
+                for alternative in dep {
+                    let target = *nodes.entry(alternative.package_num).or_insert_with(|| {
+                        worklist.push_back((alternative.package_num, d + 1));
+                        graph.add_node(DepGraphNode::Package(alternative.package_num))
+                    });
+                    let label = alternative.rel_version.as_ref().map(|(rel, ver)| format!("{} {}", rel, ver));
+                    graph.add_edge(source, target, label);
+                }
+            }
+        }
+        return Ok(graph);
+    }
+
+    /// Whether package_num is installed, available (in the Packages index only) or missing (neither).
+    pub fn package_status(&self, package_num: i32) -> PackageStatus {
+        if self.installed_debvers.contains_key(&package_num) {
+            return PackageStatus::Installed;
+        }
+        if self.available_debvers.contains_key(&package_num) {
+            return PackageStatus::Available;
+        }
+        return PackageStatus::Missing;
+    }
+
+    fn node_label(&self, node: DepGraphNode) -> String {
+        return match node {
+            DepGraphNode::Or(_, _) => String::from("|"),
+            DepGraphNode::Package(p) => match self.installed_debvers.get(&p).or_else(|| self.available_debvers.get(&p)) {
+                Some(v) => format!("{}\n{}", self.get_package_name(p), v),
+                None => self.get_package_name(p).to_string()
+            }
+        }
+    }
+
+    /// Writes graph (from dependency_graph) as a Graphviz digraph: installed packages are green, available ones white,
+    /// missing ones red, and OR-nodes grey diamonds.
+    pub fn write_dot(&self, graph: &DepGraph, out: &mut impl Write) -> io::Result<()> {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+        writeln!(out, "digraph dependencies {{")?;
+        writeln!(out, "    node [style=filled];")?;
+        for n in graph.node_indices() {
+            let label = escape(&self.node_label(graph[n]));
+            match graph[n] {
+                DepGraphNode::Or(_, _) => writeln!(out, "    n{} [label=\"{}\", shape=diamond, fillcolor=lightgrey];", n.index(), label)?,
+                DepGraphNode::Package(p) => writeln!(out, "    n{} [label=\"{}\", shape=box, fillcolor={}];",
+                    n.index(), label, self.package_status(p).dot_color())?
+            }
+        }
+        for e in graph.edge_references() {
+            match e.weight() {
+                Some(constraint) => writeln!(out, "    n{} -> n{} [label=\"{}\"];", e.source().index(), e.target().index(), escape(constraint))?,
+                None => writeln!(out, "    n{} -> n{};", e.source().index(), e.target().index())?
+            }
+        }
+        return writeln!(out, "}}");
+    }
+
+    /// Writes graph (from dependency_graph) as GraphML. Nodes have a label, a kind ("package" or "or") and, for packages,
+    /// a status ("installed", "available" or "missing") and a matching color; edges have the version constraint.
+    pub fn write_graphml(&self, graph: &DepGraph, out: &mut impl Write) -> io::Result<()> {
+        let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\n', " ");
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">")?;
+        writeln!(out, "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>")?;
+        writeln!(out, "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>")?;
+        writeln!(out, "  <key id=\"status\" for=\"node\" attr.name=\"status\" attr.type=\"string\"/>")?;
+        writeln!(out, "  <key id=\"color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>")?;
+        writeln!(out, "  <key id=\"constraint\" for=\"edge\" attr.name=\"constraint\" attr.type=\"string\"/>")?;
+        writeln!(out, "  <graph id=\"dependencies\" edgedefault=\"directed\">")?;
+        for n in graph.node_indices() {
+            let label = escape(&self.node_label(graph[n]));
+            match graph[n] {
+                DepGraphNode::Or(_, _) => writeln!(out,
+                    "    <node id=\"n{}\"><data key=\"label\">{}</data><data key=\"kind\">or</data><data key=\"color\">#d3d3d3</data></node>",
+                    n.index(), label)?,
+                DepGraphNode::Package(p) => {
+                    let status = self.package_status(p);
+                    writeln!(out,
+                        "    <node id=\"n{}\"><data key=\"label\">{}</data><data key=\"kind\">package</data><data key=\"status\">{}</data><data key=\"color\">{}</data></node>",
+                        n.index(), label, status.label(), status.rgb_color())?
+                }
+            }
+        }
+        for e in graph.edge_references() {
+            match e.weight() {
+                Some(constraint) => writeln!(out, "    <edge source=\"n{}\" target=\"n{}\"><data key=\"constraint\">{}</data></edge>",
+                    e.source().index(), e.target().index(), escape(constraint))?,
+                None => writeln!(out, "    <edge source=\"n{}\" target=\"n{}\"/>", e.source().index(), e.target().index())?
+            }
+        }
+        writeln!(out, "  </graph>")?;
+        return writeln!(out, "</graphml>");
+    }
+}
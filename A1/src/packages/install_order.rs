@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use petgraph::Graph;
+use petgraph::algo::{tarjan_scc, toposort};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{NoSuchPackage, Render};
+
+// an edge from a package to one of its (pre-)dependencies
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edge { PreDepends, Depends }
+
+impl Packages {
+    /// Orders the packages that compute_how_to_install picks for package_name, and package_name itself, into the unpack
+    /// and configure steps of an installation, the way dpkg does it: a package's Pre-Depends are configured before it is
+    /// unpacked, its Depends before it is configured. A strongly connected component of the dependency graph is a cycle
+    /// that can't be ordered; like dpkg, we unpack all of its packages, then configure them, ignoring the Depends that
+    /// this goes against. A Pre-Depends inside a cycle is still honored (its target is unpacked and configured first);
+    /// a cycle of Pre-Depends can't be installed, and is reported as a problem, and so is what compute_install_plan
+    /// can't satisfy. Packages that aren't available are left out of the steps, and reported.
+    pub fn install_order(&self, package_name: &str) -> Result<InstallOrder, NoSuchPackage> {
+        let root = self.find_package_num(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        let plan = self.compute_install_plan(package_name);
+        let mut packages = plan.install.clone();
+        packages.push(root);
+        // there is nothing to unpack for a package that isn't available; it is only reported
+        packages.retain(|p| self.available_debvers.contains_key(p));
+        let mut order = self.order_installation(package_name, &packages);
+        let mut problems = self.plan_problems(&plan);
+        if !self.available_debvers.contains_key(&root) {
+            problems.insert(0, format!("{} is not available", package_name));
+        }
+        order.problems.splice(0..0, problems);
+        return Ok(order);
+    }
+
+    /// Orders the installation of packages like install_order. Their Depends and Pre-Depends are taken to be satisfied
+    /// by each other or by the installed packages. label names the result.
+    pub fn order_installation(&self, label: &str, packages: &[i32]) -> InstallOrder {
+        let position : HashMap<i32, usize> = packages.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+
+        let mut order = InstallOrder { package : label.to_string(), steps : vec![], cycles : vec![], problems : vec![] };
+        let mut graph : Graph<i32, Edge> = Graph::new();
+        let nodes : HashMap<i32, NodeIndex> = packages.iter().map(|p| (*p, graph.add_node(*p))).collect();
+        for p in packages {
+            let relations = [(Edge::PreDepends, self.pre_dependencies.get(p)), (Edge::Depends, self.dependencies.get(p))];
+            for &(kind, deps) in relations.iter() {
+                for dep in deps.map_or(&[][..], |d| &d[..]) {
+                    // an alternative that is being installed goes first; otherwise an installed one does
+                    match dep.iter().find(|a| nodes.contains_key(&a.package_num)) {
+                        Some(a) if a.package_num == *p => (),
+                        Some(a) => { graph.add_edge(nodes[p], nodes[&a.package_num], kind); }
+                        None => ()
+                    }
+                }
+            }
+        }
+
+        // tarjan_scc lists the components in reverse topological order: dependencies first
+        for scc in tarjan_scc(&graph) {
+            let mut members : Vec<i32> = scc.iter().map(|n| graph[*n]).collect();
+            members.sort_by_key(|p| position[p]);
+            if members.len() == 1 {
+                self.push_step(&mut order, StepAction::Unpack, members[0]);
+                self.push_step(&mut order, StepAction::Configure, members[0]);
+            } else {
+                self.order_cycle(&graph, &nodes, &members, &mut order);
+            }
+        }
+        return order;
+    }
+
+    fn push_step(&self, order: &mut InstallOrder, action: StepAction, package_num: i32) {
+        order.steps.push(InstallStep {
+            action,
+            package : self.get_package_name(package_num).to_string(),
+            version : self.available_debvers.get(&package_num).map(|v| v.to_string()),
+        });
+    }
+
+    // members is a strongly connected component of graph, with more than one package
+    fn order_cycle(&self, graph: &Graph<i32, Edge>, nodes: &HashMap<i32, NodeIndex>, members: &[i32], order: &mut InstallOrder) {
+        let edges_within = |kind: Edge| {
+            let mut edges = vec![];
+            for p in members {
+                for e in graph.edges(nodes[p]) {
+                    let target = graph[e.target()];
+                    if *e.weight() == kind && members.contains(&target) {
+                        edges.push((*p, target));
+                    }
+                }
+            }
+            edges
+        };
+
+        // the targets of Pre-Depends inside the cycle are installed first, each after its own Pre-Depends targets
+        let pre_depends = edges_within(Edge::PreDepends);
+        let mut pre_graph : Graph<i32, ()> = Graph::new();
+        let pre_nodes : HashMap<i32, NodeIndex> = members.iter().map(|p| (*p, pre_graph.add_node(*p))).collect();
+        for (p, target) in &pre_depends {
+            pre_graph.add_edge(pre_nodes[p], pre_nodes[target], ());
+        }
+        let pre_order : Vec<i32> = match toposort(&pre_graph, None) {
+            Ok(sorted) => sorted.iter().rev().map(|n| pre_graph[*n]).collect(),
+            Err(_) => {
+                order.problems.push(format!("the Pre-Depends among {} form a cycle, so they can't be installed",
+                    members.iter().map(|p| self.get_package_name(*p)).collect::<Vec<_>>().join(", ")));
+                members.to_vec()
+            }
+        };
+        let early : Vec<i32> = pre_order.into_iter().filter(|p| pre_depends.iter().any(|(_, target)| target == p)).collect();
+        let rest : Vec<i32> = members.iter().copied().filter(|p| !early.contains(p)).collect();
+        for p in &early {
+            self.push_step(order, StepAction::Unpack, *p);
+            self.push_step(order, StepAction::Configure, *p);
+        }
+        for p in &rest {
+            self.push_step(order, StepAction::Unpack, *p);
+        }
+        for p in &rest {
+            self.push_step(order, StepAction::Configure, *p);
+        }
+
+        let configured : Vec<i32> = early.iter().chain(rest.iter()).copied().collect();
+        let configured_at = |p: &i32| configured.iter().position(|q| q == p).unwrap();
+        let ignored = edges_within(Edge::Depends)
+            .into_iter()
+            .filter(|(p, target)| configured_at(target) > configured_at(p))
+            .map(|(p, target)| format!("{} depends on {}", self.get_package_name(p), self.get_package_name(target)))
+            .collect();
+        order.cycles.push(CycleBreak {
+            packages : members.iter().map(|p| self.get_package_name(*p).to_string()).collect(),
+            ignored,
+        });
+    }
+}
+
+/// Result of the install-order query: the unpack and configure steps, in order.
+#[derive(Serialize)]
+pub struct InstallOrder {
+    pub package : String,
+    pub steps : Vec<InstallStep>,
+    /// dependency cycles, and the Depends that their order ignores
+    pub cycles : Vec<CycleBreak>,
+    /// dependencies that can't be satisfied, and Pre-Depends that can't be honored
+    pub problems : Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct InstallStep {
+    pub action : StepAction,
+    pub package : String,
+    /// None if the package isn't in the Packages index
+    pub version : Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StepAction {
+    Unpack,
+    Configure,
+}
+
+#[derive(Serialize)]
+pub struct CycleBreak {
+    pub packages : Vec<String>,
+    pub ignored : Vec<String>,
+}
+
+impl Render for InstallOrder {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Install order for {}:", self.package)?;
+        for (i, step) in self.steps.iter().enumerate() {
+            let action = match step.action { StepAction::Unpack => "unpack", StepAction::Configure => "configure" };
+            match &step.version {
+                Some(version) => writeln!(out, "{}. {} {} ({})", i + 1, action, step.package, version)?,
+                None => writeln!(out, "{}. {} {}", i + 1, action, step.package)?
+            }
+        }
+        for c in &self.cycles {
+            writeln!(out, "cycle {}: unpacked together, then configured; ignoring {}", c.packages.join(", "),
+                if c.ignored.is_empty() { String::from("nothing") } else { c.ignored.join(", ") })?;
+        }
+        for p in &self.problems {
+            writeln!(out, "problem: {}", p)?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::debversion::DebianVersionNum;
+use crate::packages::{ConflictIndex, ConflictRef, NoSuchPackage, PlannedVersion, Render, SizeSummary, VersionChange};
+use crate::packages::deps_available::version_satisfies;
+use crate::packages::sat::{Lit, Solver};
+
+// the unsatisfiable-core search re-solves the problem once per clause; skip it for huge problems
+const MAX_CORE_CLAUSES : usize = 5000;
+
+// One SAT variable per candidate version of a package. An installed version is kept as it is: like dep_is_satisfied,
+// we take its dependencies to be satisfied already. The available version brings its Depends and Pre-Depends with it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Candidate { Keep, Available }
+
+struct VersionVar {
+    package_num : i32,
+    candidate : Candidate,
+}
+
+// why a clause is in the problem; used to explain unsatisfiable requests
+enum ClauseOrigin {
+    Request(i32),
+    // the var whose Depends or Pre-Depends clause this is, and the clause's index (see Packages::install_clause)
+    Depends(usize, usize),
+    OneVersion(i32),
+    KeepInstalled(i32),
+    // the two vars can't both be installed
+    Conflict(usize, usize, ConflictRef),
+}
+
+struct Clause {
+    lits : Vec<Lit>,
+    guide : bool,
+    origin : ClauseOrigin,
+}
+
+struct Encoding<'a> {
+    packages : &'a Packages,
+    conflict_index : &'a ConflictIndex,
+    // ignore the installed packages: only the available versions are candidates (see installable_in_archive)
+    archive_only : bool,
+    vars : Vec<VersionVar>,
+    // vars of each package in the problem, the installed version first
+    vars_of : HashMap<i32, Vec<usize>>,
+    clauses : Vec<Clause>,
+    worklist : VecDeque<i32>,
+}
+
+impl<'a> Encoding<'a> {
+    fn new(packages: &'a Packages, conflict_index: &'a ConflictIndex, archive_only: bool) -> Encoding<'a> {
+        Encoding {
+            packages,
+            conflict_index,
+            archive_only,
+            vars : vec![],
+            vars_of : HashMap::new(),
+            clauses : vec![],
+            worklist : VecDeque::new(),
+        }
+    }
+
+    fn version(&self, var: usize) -> &DebianVersionNum {
+        let v = &self.vars[var];
+        return match v.candidate {
+            Candidate::Keep => &self.packages.installed_debvers[&v.package_num],
+            Candidate::Available => &self.packages.available_debvers[&v.package_num]
+        }
+    }
+
+    // creates the vars of package_num the first time it is mentioned, and queues it for encoding its constraints
+    fn vars_of(&mut self, package_num: i32) -> Vec<usize> {
+        if let Some(vs) = self.vars_of.get(&package_num) {
+            return vs.clone();
+        }
+        let installed = if self.archive_only { None } else { self.packages.installed_debvers.get(&package_num) };
+        let available = self.packages.available_debvers.get(&package_num);
+        let mut vs = vec![];
+        if installed.is_some() {
+            vs.push(Candidate::Keep);
+        }
+        if available.is_some() && available != installed {
+            vs.push(Candidate::Available);
+        }
+        let vs : Vec<usize> = vs.into_iter().map(|candidate| {
+            self.vars.push(VersionVar { package_num, candidate });
+            self.vars.len() - 1
+        }).collect();
+        self.vars_of.insert(package_num, vs.clone());
+        self.worklist.push_back(package_num);
+        return vs;
+    }
+
+    fn add_request(&mut self, package_num: i32) {
+        let lits = self.vars_of(package_num).into_iter().map(Lit::positive).collect();
+        self.clauses.push(Clause { lits, guide : true, origin : ClauseOrigin::Request(package_num) });
+    }
+
+    // encodes the constraints of every package reachable from the requests, and of the installed packages that conflict
+    // with them (unless archive_only)
+    fn close(&mut self) {
+        while let Some(package_num) = self.worklist.pop_front() {
+            let vs = self.vars_of[&package_num].clone();
+            if vs.len() == 2 {
+                self.clauses.push(Clause {
+                    lits : vs.iter().map(|v| Lit::negative(*v)).collect(),
+                    guide : false,
+                    origin : ClauseOrigin::OneVersion(package_num),
+                });
+            }
+            if !self.archive_only && self.packages.installed_debvers.contains_key(&package_num) {
+                self.clauses.push(Clause {
+                    lits : vs.iter().map(|v| Lit::positive(*v)).collect(),
+                    guide : true,
+                    origin : ClauseOrigin::KeepInstalled(package_num),
+                });
+            }
+            if !self.archive_only {
+                for v in vs.iter() {
+                    let installed = &self.packages.installed_debvers;
+                    for (other, _) in self.packages.conflicts_between(self.conflict_index, package_num, self.version(*v), |p| installed.get(&p)) {
+                        self.vars_of(other);
+                    }
+                }
+            }
+            let packages = self.packages;
+            for v in vs {
+                if self.vars[v].candidate == Candidate::Keep { continue; }
+                for (i, dep) in packages.install_clauses(package_num).enumerate() {
+                    // prefer alternatives that are already installed, then go by the order of the alternatives
+                    let (mut keep, mut install) = (vec![], vec![]);
+                    for alternative in dep {
+                        for w in self.vars_of(alternative.package_num) {
+                            if version_satisfies(self.version(w), &alternative.rel_version) {
+                                match self.vars[w].candidate {
+                                    Candidate::Keep => keep.push(Lit::positive(w)),
+                                    Candidate::Available => install.push(Lit::positive(w))
+                                }
+                            }
+                        }
+                    }
+                    let mut lits = vec![Lit::negative(v)];
+                    lits.extend(keep);
+                    lits.extend(install);
+                    self.clauses.push(Clause { lits, guide : true, origin : ClauseOrigin::Depends(v, i) });
+                }
+            }
+        }
+        self.add_conflicts();
+    }
+
+    // one clause per pair of versions in the problem that a Conflicts or Breaks entry rules out
+    fn add_conflicts(&mut self) {
+        let mut package_nums : Vec<i32> = self.vars_of.keys().copied().collect();
+        package_nums.sort();
+        for package_num in package_nums {
+            for &v in &self.vars_of[&package_num] {
+                for (i, c) in self.packages.declared_conflicts(package_num, self.version(v)).iter().enumerate() {
+                    if c.target.package_num == package_num { continue; }
+                    for &w in self.vars_of.get(&c.target.package_num).map_or(&[][..], |ws| &ws[..]) {
+                        if version_satisfies(self.version(w), &c.target.rel_version) {
+                            self.clauses.push(Clause {
+                                lits : vec![Lit::negative(v), Lit::negative(w)],
+                                guide : false,
+                                origin : ClauseOrigin::Conflict(v, w, ConflictRef { declared_by : package_num, index : i }),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn solve(&self, clauses: &[&Clause]) -> Option<Vec<bool>> {
+        let mut solver = Solver::new();
+        for _ in &self.vars {
+            solver.new_var();
+        }
+        for c in clauses {
+            solver.add_clause(&c.lits, c.guide);
+        }
+        return solver.solve();
+    }
+
+    // deletion-based search for a minimal set of clauses that is still unsatisfiable; all of them if there are more
+    // than max_clauses
+    fn unsat_core(&self, max_clauses: usize) -> Vec<&Clause> {
+        let mut core : Vec<&Clause> = self.clauses.iter().collect();
+        if core.len() > max_clauses {
+            return core;
+        }
+        let mut i = 0;
+        while i < core.len() {
+            let mut without = core.clone();
+            without.remove(i);
+            if self.solve(&without).is_none() {
+                core = without;
+            } else {
+                i += 1;
+            }
+        }
+        return core;
+    }
+
+    fn explain(&self, clause: &Clause) -> String {
+        let p = self.packages;
+        return match clause.origin {
+            ClauseOrigin::Request(pnum) if clause.lits.is_empty() =>
+                format!("{} is requested, but no version of it is available or installed", p.get_package_name(pnum)),
+            ClauseOrigin::Request(pnum) => format!("{} is requested", p.get_package_name(pnum)),
+            ClauseOrigin::Depends(v, i) => {
+                let pnum = self.vars[v].package_num;
+                let (dep, relation) = p.install_clause(pnum, i);
+                let unsatisfiable = if clause.lits.len() == 1 { " (no available or installed version satisfies this)" } else { "" };
+                format!("{} {} {} {}{}", p.get_package_name(pnum), self.version(v), relation, p.dep2str(dep), unsatisfiable)
+            }
+            ClauseOrigin::OneVersion(pnum) => {
+                let vs = &self.vars_of[&pnum];
+                format!("only one version of {} can be installed ({} is installed, {} is available)",
+                    p.get_package_name(pnum), self.version(vs[0]), self.version(vs[1]))
+            }
+            ClauseOrigin::KeepInstalled(pnum) => format!("{} is installed and can't be removed", p.get_package_name(pnum)),
+            ClauseOrigin::Conflict(v, w, conflict) => format!("{} ({} {}, {} {})", p.conflict2str(conflict),
+                p.get_package_name(self.vars[v].package_num), self.version(v), p.get_package_name(self.vars[w].package_num), self.version(w))
+        }
+    }
+}
+
+impl Packages {
+    /// Finds a consistent set of package versions to install so that package_name is installed, or proves that
+    /// none exists. Unlike compute_how_to_install, this is a complete search (a CDCL SAT solver over one variable
+    /// per installed or available version): a choice between alternatives is revisited when it leads to a dead end,
+    /// every selected version must satisfy the version constraints placed on it, and no two selected versions may
+    /// conflict (Conflicts or Breaks). Installed packages that the request reaches may be upgraded, but not removed.
+    /// If there is no solution, the plan lists a minimal set of constraints that can't all hold; when that includes
+    /// "X is installed and can't be removed", the request could only be met by removing X.
+    pub fn solve_install(&self, package_name: &str) -> Result<SolverPlan, NoSuchPackage> {
+        let package_num = self.find_package_num(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        let index = self.conflict_index();
+        let mut enc = Encoding::new(self, &index, false);
+        enc.add_request(package_num);
+        enc.close();
+
+        let mut plan = SolverPlan {
+            package : package_name.to_string(),
+            satisfiable : false,
+            install : vec![],
+            upgrade : vec![],
+            conflict : vec![],
+            sizes : self.size_summary(&[], &[], false),
+        };
+        let all_clauses : Vec<&Clause> = enc.clauses.iter().collect();
+        match enc.solve(&all_clauses) {
+            Some(assignment) => {
+                plan.satisfiable = true;
+                let mut changed = vec![];
+                for (v, selected) in assignment.iter().enumerate() {
+                    let var = &enc.vars[v];
+                    if !selected || var.candidate == Candidate::Keep { continue; }
+                    changed.push(var.package_num);
+                    let name = self.get_package_name(var.package_num).to_string();
+                    match self.installed_debvers.get(&var.package_num) {
+                        None => plan.install.push(PlannedVersion { package : name, version : enc.version(v).to_string() }),
+                        Some(installed) => plan.upgrade.push(VersionChange {
+                            package : name,
+                            from : installed.to_string(),
+                            to : enc.version(v).to_string(),
+                        })
+                    }
+                }
+                plan.sizes = self.size_summary(&changed, &[], false);
+            }
+            None => {
+                plan.conflict = enc.unsat_core(MAX_CORE_CLAUSES).iter().map(|c| enc.explain(c)).collect();
+            }
+        }
+        return Ok(plan);
+    }
+
+    /// Checks whether the available version of package_num could be installed on a system with nothing installed,
+    /// i.e. from the Packages index alone, with the same complete search as solve_install. index is conflict_index(),
+    /// passed in so that it can be shared between calls. Returns None if it can be installed, otherwise a set of
+    /// constraints that can't all hold: a minimal one if the problem has at most max_core_clauses constraints.
+    pub fn installable_in_archive(&self, package_num: i32, index: &ConflictIndex, max_core_clauses: usize) -> Option<Vec<String>> {
+        let mut enc = Encoding::new(self, index, true);
+        enc.add_request(package_num);
+        enc.close();
+        let all_clauses : Vec<&Clause> = enc.clauses.iter().collect();
+        if enc.solve(&all_clauses).is_some() {
+            return None;
+        }
+        return Some(enc.unsat_core(max_core_clauses).iter().map(|c| enc.explain(c)).collect());
+    }
+}
+
+/// Result of the solve-install query. If satisfiable is false, install and upgrade are empty and conflict lists
+/// constraints that can't all hold at once.
+#[derive(Serialize)]
+pub struct SolverPlan {
+    pub package : String,
+    pub satisfiable : bool,
+    pub install : Vec<PlannedVersion>,
+    /// installed packages that move to their available version (an upgrade, or a downgrade if that one is older)
+    pub upgrade : Vec<VersionChange>,
+    pub conflict : Vec<String>,
+    /// of installing and upgrading
+    pub sizes : SizeSummary,
+}
+
+impl Render for SolverPlan {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Package {}:", self.package)?;
+        if !self.satisfiable {
+            writeln!(out, "{:?} can't be installed; these constraints conflict:", self.package)?;
+            for c in &self.conflict {
+                writeln!(out, "- {}", c)?;
+            }
+            return Ok(());
+        }
+        let install = self.install.iter().map(|p| format!("{} ({})", p.package, p.version)).collect::<Vec<_>>();
+        let upgrade = self.upgrade.iter().map(|c| format!("{} ({} -> {})", c.package, c.from, c.to)).collect::<Vec<_>>();
+        writeln!(out, "{:?} to install: {:?}", self.package, install.join(", "))?;
+        if !upgrade.is_empty() {
+            writeln!(out, "{:?} to upgrade: {:?}", self.package, upgrade.join(", "))?;
+        }
+        return self.sizes.render(out);
+    }
+}
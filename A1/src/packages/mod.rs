@@ -1,60 +1,170 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io;
 use std::path::Path;
-use std::sync::atomic::{AtomicI32, Ordering};
 
 use itertools::Itertools;
 
-use rpkg::debversion;
-use rpkg::debversion::{DebianVersionNum,VersionRelation};
+use crate::debversion;
+use crate::debversion::{DebianVersionNum,VersionRelation};
 
 mod deps_available;
 mod solvers;
 mod parsers;
 mod async_fns;
+mod suggest;
+mod reports;
+mod upgradable;
+mod sat;
+mod install_solver;
+mod conflicts;
+mod upgrade_plan;
+mod remove_plan;
+mod install_order;
+mod graph_export;
+mod policy;
+mod why;
+mod why_not;
+mod distcheck;
+mod versioned_closure;
+mod closure;
+mod cycles;
+mod base_system;
+mod sizes;
+mod sources;
+mod weigh;
+mod top_depended;
 
 use crate::packages::async_fns::AsyncState;
+use crate::packages::suggest::NameIndex;
+use crate::packages::deps_available::version_satisfies;
 
-static PACKAGE_COUNTER: AtomicI32 = AtomicI32::new(0);
+pub use crate::packages::deps_available::{DependencyAvailability, DepsAvailabilityReport, SatisfiedBy};
+pub use crate::packages::solvers::{HowToInstall, Removal, SkippedSoft, SoftChoice, SoftDependencies, Unsatisfiable};
+pub use crate::packages::parsers::{InstallRequest, parse_install_requests, read_install_requests};
+pub use crate::packages::async_fns::{QueuedRequest, VerificationOutcome, VerificationReport, VerificationResult};
+pub use crate::packages::reports::*;
+pub use crate::packages::upgradable::{UpgradableEntry, UpgradableOptions, UpgradableReport, UpgradableSort, UpgradeKind};
+pub use crate::packages::install_solver::SolverPlan;
+pub use crate::packages::conflicts::{ConflictIndex, ConflictRef};
+pub use crate::packages::upgrade_plan::{KeptBack, UpgradePlan};
+pub use crate::packages::remove_plan::{BrokenByRemoval, OrphanedPackage, RemovalImpact, SatisfiedByAlternative};
+pub use crate::packages::install_order::{CycleBreak, InstallOrder, InstallStep, StepAction};
+pub use crate::packages::graph_export::{DepGraph, DepGraphNode, GraphExported, GraphFormat, PackageStatus};
+pub use crate::packages::policy::*;
+pub use crate::packages::why::{WhyReport, WhyStep};
+pub use crate::packages::why_not::{AlternativeCause, AlternativeStatus, UnsatisfiedClause, WhyNotReport};
+pub use crate::packages::distcheck::{BrokenPackage, DistcheckReport, DistcheckSummary};
+pub use crate::packages::versioned_closure::{PlacedConstraint, VersionedDepSolution, VersionedPackage};
+pub use crate::packages::closure::{ClosureEngine, ClosureSize, ClosureSizeReport};
+pub use crate::packages::cycles::{CycleEdge, CycleReport, DependencyCycle};
+pub use crate::packages::base_system::BaseSystem;
+pub use crate::packages::sizes::{PackageSize, SizeSummary};
+pub use crate::packages::sources::{BuildAlternative, BuildDependency, BuildDeps, BuildField, BuildRelation, SourcePackage};
+pub use crate::packages::weigh::{DependencyWeight, Weight};
+pub use crate::packages::top_depended::{ReverseClosure, TopDepended};
 
+/// The package database: available and installed versions, dependencies and md5sums, keyed by package number.
+/// Every package name that is mentioned anywhere (including only as a dependency) gets a number.
 pub struct Packages {
     dependencies : HashMap<i32,Vec<Dependency>>,
+    pre_dependencies : HashMap<i32,Vec<Dependency>>,
+    recommends : HashMap<i32,Vec<Dependency>>,
+    suggests : HashMap<i32,Vec<Dependency>>,
+    conflicts : HashMap<i32,Vec<Conflict>>,
     md5sums : HashMap<i32,String>,
+    sections : HashMap<i32,String>,
+    // Section of the installed version, from the installed packages file
+    installed_sections : HashMap<i32,String>,
+    priorities : HashMap<i32,String>,
+    // Size of the available version's .deb, in bytes
+    download_sizes : HashMap<i32,u64>,
+    // Installed-Size of the available and the installed version, in KiB
+    available_disk_sizes : HashMap<i32,u64>,
+    installed_disk_sizes : HashMap<i32,u64>,
+    // source package name -> its stanza of the Sources index; source names are separate from binary package names
+    sources : HashMap<String,SourcePackage>,
+    // the source package names, numbered in the order they were first seen, and their own trigram index
+    source_names : Vec<String>,
+    source_name_index : NameIndex,
+    // packages whose available version is Essential: yes
+    essential : HashSet<i32>,
     available_debvers : HashMap<i32,DebianVersionNum>,
     installed_debvers : HashMap<i32,DebianVersionNum>,
     package_name_to_num : HashMap<String, i32>,
     package_num_to_name : HashMap<i32, String>,
+    name_index : NameIndex,
     async_state : AsyncState,
 }
 
 // Dependency([X, Y, Z]) means X|Y|Z
+#[derive(Clone)]
 pub struct RelVersionedPackageNum {
-    package_num : i32,
-    rel_version : Option<(VersionRelation, String)>
+    pub package_num : i32,
+    pub rel_version : Option<(VersionRelation, String)>
 }
 pub type Dependency = Vec<RelVersionedPackageNum>;
 
+/// Which field a Conflict comes from. We treat both as "can't be installed at the same time".
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConflictKind { Conflicts, Breaks }
+
+/// One entry of a package's Conflicts or Breaks field.
+pub struct Conflict {
+    pub kind : ConflictKind,
+    pub target : RelVersionedPackageNum,
+}
+
+impl Default for Packages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Packages {
     pub fn new() -> Packages {
         Packages { 
             dependencies : HashMap::new(), 
+            pre_dependencies : HashMap::new(),
+            recommends : HashMap::new(),
+            suggests : HashMap::new(),
+            conflicts : HashMap::new(),
             md5sums : HashMap::new(),
+            sections : HashMap::new(),
+            installed_sections : HashMap::new(),
+            priorities : HashMap::new(),
+            download_sizes : HashMap::new(),
+            available_disk_sizes : HashMap::new(),
+            installed_disk_sizes : HashMap::new(),
+            sources : HashMap::new(),
+            source_names : vec![],
+            source_name_index : NameIndex::new(),
+            essential : HashSet::new(),
             available_debvers : HashMap::new(),
             installed_debvers : HashMap::new(),
             package_name_to_num : HashMap::new(), 
             package_num_to_name : HashMap::new(),
+            name_index : NameIndex::new(),
             async_state : AsyncState::new(),
         }
     }
 
     // next few functions manipulate the list of packages and the name/number interface
+    /// Returns every known package name, in no particular order.
     pub fn get_package_names(&self) -> Vec<&str> {
         self.package_name_to_num.keys().map(|x| &x[..]).collect()
     }
 
-    fn get_package_name(&self, package_num: i32) -> &str {
+    /// Returns the name of a package number handed out by this database (e.g. in a query result).
+    /// Panics if package_num doesn't belong to this database.
+    pub fn get_package_name(&self, package_num: i32) -> &str {
         return self.package_num_to_name.get(&package_num).unwrap();
     }
 
+    /// Returns the package number for package_name, or None if the name is unknown.
+    pub fn find_package_num(&self, package_name: &str) -> Option<i32> {
+        return self.package_name_to_num.get(package_name).copied();
+    }
+
     // panics if package_name doesn't already exist
     fn get_package_num(&self, package_name: &str) -> &i32 {
         return self.package_name_to_num.get(package_name).unwrap();
@@ -63,22 +173,25 @@ impl Packages {
     // inserts package_name into package_name_to_num if it doesn't already exist
     fn get_package_num_inserting(&mut self, package_name: &str) -> i32 {
         if !self.package_name_to_num.contains_key(package_name) {
-            let pnum = PACKAGE_COUNTER.load(Ordering::SeqCst);
+            // numbers are dense and per-database, so several databases can coexist
+            let pnum = self.package_num_to_name.len() as i32;
             self.package_name_to_num.insert(String::from(package_name), pnum);
             self.package_num_to_name.insert(pnum, String::from(package_name));
+            self.name_index.insert(package_name, pnum);
             self.dependencies.insert(pnum, vec![]);
-            PACKAGE_COUNTER.fetch_add(1, Ordering::SeqCst);
             return pnum;
         } else {
             return *self.package_name_to_num.get(package_name).unwrap();
         }
     }
 
+    /// Returns true if package_name is known, either as an available/installed package or as a dependency.
     pub fn package_exists(&self, package_name: &str) -> bool {
         return self.package_name_to_num.contains_key(package_name);
     }
 
     // accessor methods for various maps
+    /// Returns the version of package_name in the loaded Packages index, if any.
     pub fn get_available_debver(&self, package_name: &str) -> Option<&DebianVersionNum> {
         let package_num = self.package_name_to_num.get(package_name);
         return match package_num {
@@ -90,6 +203,7 @@ impl Packages {
         }
     }
 
+    /// Returns the installed version of package_name, if any.
     pub fn get_installed_debver(&self, package_name: &str) -> Option<&DebianVersionNum> {
         let package_num = self.package_name_to_num.get(package_name);
         return match package_num {
@@ -101,6 +215,7 @@ impl Packages {
         }
     }
 
+    /// Returns the MD5sum of the available version of package_name, if any.
     pub fn get_md5sum(&self, package_name: &str) -> Option<&str> {
         let package_num = self.package_name_to_num.get(package_name);
         return match package_num {
@@ -112,98 +227,272 @@ impl Packages {
         }
     }
 
+    /// Returns the Section of package_name in the loaded Packages index, if any.
+    pub fn get_section(&self, package_name: &str) -> Option<&str> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return self.sections.get(package_num).map(|s| &s[..]);
+    }
+
+    /// Returns the Priority (required, important, standard, optional or extra) of package_name in the loaded Packages
+    /// index, if any.
+    pub fn get_priority(&self, package_name: &str) -> Option<&str> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return self.priorities.get(package_num).map(|s| &s[..]);
+    }
+
+    /// Returns the Size (of the .deb, in bytes) of the available version of package_name, if known.
+    pub fn get_download_size(&self, package_name: &str) -> Option<u64> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return self.download_sizes.get(package_num).copied();
+    }
+
+    /// Returns the Installed-Size (in KiB) of the available version of package_name, if known.
+    pub fn get_installed_size(&self, package_name: &str) -> Option<u64> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return self.available_disk_sizes.get(package_num).copied();
+    }
+
+    /// Returns true if package_name is marked Essential: yes in the loaded Packages index.
+    pub fn is_essential(&self, package_name: &str) -> bool {
+        return self.package_name_to_num.get(package_name).is_some_and(|p| self.essential.contains(p));
+    }
+
+    /// Returns the dependencies (Depends) of package_name, or None if the name is unknown.
+    pub fn get_dependencies(&self, package_name: &str) -> Option<&[Dependency]> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return self.dependencies.get(package_num).map(|deps| &deps[..]);
+    }
+
+    /// Returns the Pre-Depends of package_name, or None if the name is unknown.
+    pub fn get_pre_dependencies(&self, package_name: &str) -> Option<&[Dependency]> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return Some(self.pre_dependencies.get(package_num).map_or(&[], |deps| &deps[..]));
+    }
+
+    /// Returns the Recommends or Suggests of package_name, or None if the name is unknown.
+    pub fn get_soft_dependencies(&self, package_name: &str, relation: SoftRelation) -> Option<&[Dependency]> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return Some(self.soft_dependencies(*package_num, relation));
+    }
+
+    // the Recommends or Suggests of package_num
+    fn soft_dependencies(&self, package_num: i32, relation: SoftRelation) -> &[Dependency] {
+        let map = match relation {
+            SoftRelation::Recommends => &self.recommends,
+            SoftRelation::Suggests => &self.suggests
+        };
+        return map.get(&package_num).map_or(&[], |deps| &deps[..]);
+    }
+
+    // the number of clauses that have to hold for package_num to be installed: its Depends, then its Pre-Depends (the
+    // Planner numbers them this way)
+    fn install_clause_count(&self, package_num: i32) -> usize {
+        return self.dependencies[&package_num].len() + self.pre_dependencies.get(&package_num).map_or(0, |deps| deps.len());
+    }
+
+    // clause i of package_num in that numbering, with the relation it comes from, as in "depends on"
+    fn install_clause(&self, package_num: i32, i: usize) -> (&Dependency, &'static str) {
+        let depends = &self.dependencies[&package_num];
+        return match depends.get(i) {
+            Some(dep) => (dep, "depends on"),
+            None => (&self.pre_dependencies[&package_num][i - depends.len()], "pre-depends on")
+        };
+    }
+
+    // the clauses of package_num in that numbering
+    fn install_clauses(&self, package_num: i32) -> impl Iterator<Item = &Dependency> {
+        return self.dependencies[&package_num].iter().chain(self.pre_dependencies.get(&package_num).into_iter().flatten());
+    }
+
+    /// Returns the Conflicts and Breaks of the available version of package_name, or None if the name is unknown.
+    pub fn get_conflicts(&self, package_name: &str) -> Option<&[Conflict]> {
+        let package_num = self.package_name_to_num.get(package_name)?;
+        return Some(self.conflicts.get(package_num).map_or(&[], |c| &c[..]));
+    }
+
     // helper functions; these aren't structs so I can't make them implement Fmt::Display.
-    fn deps2str(&self, deps: &Vec<Dependency>) -> String {
+    pub fn deps2str(&self, deps: &[Dependency]) -> String {
         return deps.iter().map(|dep| self.dep2str(dep)).format(", ").to_string();
     }
 
-    fn dep2str(&self, dep: &Dependency) -> String {
+    /// Formats a dependency the way it appears in a Packages file, e.g. "gpgv | gpgv2 (>= 2.1)".
+    pub fn dep2str(&self, dep: &Dependency) -> String {
         return dep.iter().map(|d| {
             let pn = self.get_package_name(d.package_num);
             match &d.rel_version {
                 None => String::from(pn),
-                Some((rel, ver)) => format!("{} ({} {})", pn, rel.to_string(), ver)
+                Some((rel, ver)) => format!("{} ({} {})", pn, rel, ver)
             }
         }).format(" | ").to_string();
     }
 
-    // output commands
-    pub fn print_deps(&self, package_name: &str) {
+    // queries; each returns a report that the caller renders
+    /// Returns the dependencies of package_name.
+    pub fn deps(&self, package_name: &str) -> Result<DependencyList, NoSuchPackage> {
+        let deps = self.get_dependencies(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        return Ok(DependencyList {
+            package : package_name.to_string(),
+            depends : deps.iter().map(|dep| self.dep2str(dep)).collect(),
+        });
+    }
+
+    /// Returns the result of transitive_dep_solution_with for package_name, as names.
+    pub fn transitive_deps(&self, package_name: &str, policy: &dyn AlternativePolicy) -> Result<TransitiveDepSolution, NoSuchPackage> {
         if !self.package_exists(package_name) {
-            println!("no such package {}", package_name);
-            return;
+            return Err(self.no_such_package(package_name));
         }
-        let deps : &Vec<Dependency> = &*self.dependencies.get(self.get_package_num(package_name)).unwrap();
-        println!("{:?} depends on {:?}", package_name, self.deps2str(deps));
+        let dep_solution : Vec<i32> = self.transitive_dep_solution_with(package_name, policy);
+        return Ok(TransitiveDepSolution {
+            package : package_name.to_string(),
+            solution : dep_solution.iter().map(|dep| self.get_package_name(*dep).to_string()).collect(),
+        });
     }
 
-    pub fn print_transitive_dep_solution(&self, package_name: &str) {
-        if !self.package_exists(package_name) {
-            println!("no such package {}", package_name);
-            return;
+    /// Returns the result of compute_install_plan_for for the requested packages, as names and explanations. A request
+    /// with a version constraint that the installed version satisfies is left alone; one that the available version
+    /// doesn't satisfy is reported as unsatisfiable, and so are requested packages that conflict with each other.
+    /// soft says which of Recommends and Suggests to follow too.
+    pub fn how_to_install(&self, requests: &[InstallRequest], policy: &dyn AlternativePolicy, soft: SoftDependencies) -> Result<InstallPlan, NoSuchPackage> {
+        let mut seeds = vec![];
+        let mut already_satisfied = vec![];
+        let mut unsatisfiable = vec![];
+        for request in requests {
+            let package_num = self.find_package_num(&request.package).ok_or_else(|| self.no_such_package(&request.package))?;
+            let installed_ok = self.installed_debvers.get(&package_num).is_some_and(|v| version_satisfies(v, &request.rel_version));
+            let available = self.available_debvers.get(&package_num);
+            if installed_ok && (request.rel_version.is_some() || available.is_none()) {
+                already_satisfied.push(request.to_string());
+            } else if available.is_some_and(|v| version_satisfies(v, &request.rel_version)) {
+                seeds.push(package_num);
+            } else {
+                unsatisfiable.push(match available {
+                    Some(v) => format!("requested {}, but the available version is {}", request, v),
+                    None => format!("requested {}, which is not available", request)
+                });
+            }
+        }
+        let index = self.conflict_index();
+        for s in &seeds {
+            let both_requested = |p| if seeds.contains(&p) { self.available_debvers.get(&p) } else { None };
+            for (other, conflict) in self.conflicts_between(&index, *s, &self.available_debvers[s], both_requested) {
+                if *s < other {
+                    unsatisfiable.push(format!("{} (both requested)", self.conflict2str(conflict)));
+                }
+            }
         }
-        let dep_solution : Vec<i32> = self.transitive_dep_solution(package_name);
-        println!("{:?} transitive dependency solution: {:?}", package_name, dep_solution.iter().map(|dep| self.get_package_name(*dep)).format(", ").to_string());
+
+        let plan = self.compute_install_plan_for(&seeds, policy, soft);
+        let to_remove = plan.remove.iter().map(|r| PlannedRemoval {
+            package : self.get_package_name(r.package_num).to_string(),
+            installed_version : self.installed_debvers[&r.package_num].to_string(),
+            reason : self.explain_removal(r),
+        }).collect();
+        unsatisfiable.extend(self.plan_problems(&plan));
+        // the requested packages aren't in plan.install, but they get downloaded and unpacked too
+        let changed : Vec<i32> = seeds.iter().chain(plan.install.iter()).copied().collect();
+        let sizes = self.size_summary(&changed, &plan.remove.iter().map(|r| r.package_num).collect::<Vec<_>>(), false);
+        let soft_dependencies = plan.soft.iter().map(|s| SoftInstall {
+            package : self.get_package_name(s.package_num).to_string(),
+            of : self.get_package_name(s.for_package).to_string(),
+            relation : s.relation,
+            dependency : self.dep2str(&self.soft_dependencies(s.for_package, s.relation)[s.dep_index]),
+        }).collect();
+        let skipped = plan.skipped.iter().map(|s| {
+            let dep = &self.soft_dependencies(s.package_num, s.relation)[s.dep_index];
+            let why = if s.reasons.is_empty() { String::from("no alternative can be installed") } else { s.reasons.join("; ") };
+            format!("{} {} {:?}: {}", self.get_package_name(s.package_num), s.relation, self.dep2str(dep), why)
+        }).collect();
+        return Ok(InstallPlan {
+            packages : requests.iter().map(|r| r.to_string()).collect(),
+            to_install : plan.install.iter().map(|dep| self.get_package_name(*dep).to_string()).collect(),
+            requested_by : plan.install.iter()
+                .map(|p| (self.get_package_name(*p).to_string(), self.get_package_name(plan.requested_by[p]).to_string()))
+                .collect(),
+            to_remove,
+            unsatisfiable,
+            already_satisfied,
+            soft_dependencies,
+            skipped,
+            sizes,
+        });
     }
 
-    pub fn print_how_to_install(&self, package_name: &str) {
-        if !self.package_exists(package_name) {
-            println!("no such package {}", package_name);
-            return;
+    // why the plan removes r's package
+    fn explain_removal(&self, r: &Removal) -> String {
+        let mut reason = self.conflict2str(r.conflict);
+        if let Some((pnum, i)) = r.needed_for {
+            let (dep, _) = self.install_clause(pnum, i);
+            reason.push_str(&format!(" (every way to satisfy {}'s dependency {:?} requires a removal)", self.get_package_name(pnum), self.dep2str(dep)));
         }
-        println!("Package {}:", package_name);
-        let pkgs_to_install:Vec<i32> = self.compute_how_to_install(package_name);
-        println!("{:?} to install: {:?}", package_name, pkgs_to_install.iter().map(|dep| self.get_package_name(*dep)).format(", ").to_string());
+        return reason;
     }
 
-    pub fn print_info(&self, package_name: &str) {
+    // the dependency that u couldn't satisfy, and why
+    fn explain_unsatisfiable(&self, u: &Unsatisfiable) -> String {
+        let (dep, relation) = self.install_clause(u.package_num, u.dep_index);
+        let wrong_versions = u.wrong_versions.iter()
+            .map(|p| format!("{} {} is available, which doesn't satisfy it", self.get_package_name(*p), self.available_debvers[p]));
+        let unavailable = dep.iter().filter(|a| !self.available_debvers.contains_key(&a.package_num))
+            .map(|a| format!("{} is not available", self.get_package_name(a.package_num)));
+        let why = u.conflicts.iter().map(|c| self.conflict2str(*c)).chain(wrong_versions).chain(unavailable).join("; ");
+        let why = if why.is_empty() { String::from("no alternative can be installed") } else { why };
+        return format!("{} {} {:?}: {}", self.get_package_name(u.package_num), relation, self.dep2str(dep), why);
+    }
+
+    // what keeps plan from being carried out: the dependencies it couldn't satisfy, and the packages it picked that
+    // aren't available
+    fn plan_problems(&self, plan: &HowToInstall) -> Vec<String> {
+        let mut problems : Vec<String> = plan.unsatisfiable.iter().map(|u| self.explain_unsatisfiable(u)).collect();
+        for p in plan.install.iter().filter(|p| !self.available_debvers.contains_key(p)) {
+            problems.push(match plan.requested_by[p] {
+                q if q == *p => format!("{} is not available", self.get_package_name(*p)),
+                q => format!("{} is not available (needed for {})", self.get_package_name(*p), self.get_package_name(q))
+            });
+        }
+        return problems;
+    }
+
+    /// Returns everything known about package_name, integrating available and installed information.
+    pub fn info(&self, package_name: &str) -> Result<PackageInfo, NoSuchPackage> {
         if !self.package_exists(package_name) {
-            println!("no such package {}", package_name);
-            return;
+            return Err(self.no_such_package(package_name));
         }
-        println!("Package: {}", package_name);
         let a = self.get_available_debver(package_name);
         let i = self.get_installed_debver(package_name);
-        match a {
-            None => (),
-            Some(a) => {
-                println!("Version: {}", a.to_string());
-                println!("MD5Sum: {}", self.get_md5sum(package_name).unwrap().to_string());
-                println!("Depends: {}", self.deps2str(&*self.dependencies.get(self.get_package_num(package_name)).unwrap()));
-            }
-        }
-        match i {
-            None => (),
-            Some(i) => { println!("Installed-Version: {}", i.to_string()) }
-        }
-        match (a, i) {
-            (Some(aa), Some(ii)) =>
-                { println!("Newer-Available: {:?}", aa > ii); }
-            _ => ()
-        }
+        return Ok(PackageInfo {
+            package : package_name.to_string(),
+            version : a.map(|a| a.to_string()),
+            md5sum : a.and(self.get_md5sum(package_name)).map(String::from),
+            depends : a.map(|_| self.dependencies.get(self.get_package_num(package_name)).unwrap().iter().map(|dep| self.dep2str(dep)).collect()),
+            installed_version : i.map(|i| i.to_string()),
+            newer_available : match (a, i) {
+                (Some(aa), Some(ii)) => Some(aa > ii),
+                _ => None
+            },
+        });
     }
 
     // generate output for package-verifier
-    pub fn output_md5s(&self, fname: &str) {
+    /// Writes a name,version,hash CSV of all available packages to fname.
+    pub fn output_md5s(&self, fname: &str) -> io::Result<()> {
         let path = Path::new(fname);
         let mut md5s : String = "name,version,hash\n".to_owned();
         for pn in self.get_package_names() {
-            match (self.get_available_debver(pn), self.get_md5sum(pn)) {
-                (Some(v), Some(m)) => {
-                    let row = format!("{},{},{}\n",pn,v.to_string(),m);
-                    md5s.push_str(&row)
-                }
-                (_, _) => ()
+            if let (Some(v), Some(m)) = (self.get_available_debver(pn), self.get_md5sum(pn)) {
+                let row = format!("{},{},{}\n",pn,v,m);
+                md5s.push_str(&row)
             }
         }
-        std::fs::write(path, md5s).unwrap();
+        return std::fs::write(path, md5s);
     }
 
     // provided parse function to let students do the async io part independently
-    pub fn parse_csv(&mut self, filename: &str) {
-        let mut rdr = csv::Reader::from_path(filename).unwrap();
+    /// Loads available packages, versions and md5sums from a name,version,hash CSV (as written by output_md5s).
+    /// Returns the number of available packages.
+    pub fn parse_csv(&mut self, filename: &str) -> csv::Result<usize> {
+        let mut rdr = csv::Reader::from_path(filename)?;
         for line in rdr.records() {
-            let line = line.unwrap();
+            let line = line?;
             let package_name = String::from(line.get(0).unwrap());
             let debver = String::from(line.get(1).unwrap()).parse::<debversion::DebianVersionNum>().unwrap();
             let md5sum = String::from(line.get(2).unwrap());
@@ -214,6 +503,6 @@ impl Packages {
 
         }
 
-        println!("Packages available: {}", self.available_debvers.keys().len());
+        return Ok(self.available_debvers.keys().len());
     }
 }
@@ -1,98 +1,328 @@
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
+use std::str::FromStr;
 
 use regex::Regex;
 
 use crate::Packages;
-use crate::packages::{Dependency, RelVersionedPackageNum};
+use crate::packages::{BuildAlternative, BuildField, BuildRelation, Conflict, ConflictKind, Dependency, RelVersionedPackageNum, SourcePackage};
 
-use rpkg::debversion;
+use crate::debversion;
 
 const KEYVAL_REGEX : &str = r"^(?P<key>(\w|-)+): (?P<value>.+)";
 const PKGNAME_AND_VERSION_REGEX : &str = r"(?P<pkg>(\w|\.|\+|-)+)( \((?P<op>(<|=|>)(<|=|>)?) (?P<ver>.*)\))?";
+// a field whose value may be empty on the first line, and continued on the next ones (as in Sources files)
+const FIELD_REGEX : &str = r"^(?P<key>(\w|-)+):(?P<value>.*)";
+// the architecture and build profile restrictions of a build relationship, e.g. "[amd64 !i386]" and "<!nocheck>"
+const RESTRICTION_REGEX : &str = r"\[(?P<archs>[^\]]*)\]|<(?P<profiles>[^>]*)>";
+// an architecture qualifier, as in "python3:any" or "gcc:native"
+const ARCH_QUALIFIER_REGEX : &str = r"^(?P<pkg>[^\s:(]+):[\w-]+";
 
 impl Packages {
-    /// Loads packages and version numbers from a file, calling get_package_num_inserting on the package name
-    /// and inserting the appropriate value into the installed_debvers map with the parsed version number.
-    pub fn parse_installed(&mut self, filename: &str) {
+    /// Loads packages, version numbers, sections and Installed-Size from a file, calling get_package_num_inserting on the
+    /// package name and inserting the appropriate values into the installed_debvers map with the parsed version number,
+    /// and the installed_sections and installed_disk_sizes maps.
+    /// Returns the number of installed packages.
+    pub fn parse_installed(&mut self, filename: &str) -> io::Result<usize> {
         let kv_regexp = Regex::new(KEYVAL_REGEX).unwrap();
-        if let Ok(lines) = read_lines(filename) {
-            let mut current_package_num = 0;
-            for line in lines {
-                if let Ok(ip) = line {
-                    // do something with ip
-                    match kv_regexp.captures(&ip) {
-                        None => (),
-                        Some(caps) => {
-                            let (key, value) = (caps.name("key").unwrap().as_str(), caps.name("value").unwrap().as_str());
-                            if key == "Package" {
-                                current_package_num = self.get_package_num_inserting(&value);
-                            } else if key == "Version" {
-                                let debver = value.trim().parse::<debversion::DebianVersionNum>().unwrap();
-                                self.installed_debvers.insert(current_package_num, debver);
-                            }
+        let lines = read_lines(filename)?;
+        let mut current_package_num = 0;
+        for line in lines {
+            let ip = line?;
+            // do something with ip
+            match kv_regexp.captures(&ip) {
+                None => (),
+                Some(caps) => {
+                    let (key, value) = (caps.name("key").unwrap().as_str(), caps.name("value").unwrap().as_str());
+                    if key == "Package" {
+                        current_package_num = self.get_package_num_inserting(value);
+                    } else if key == "Version" {
+                        let debver = value.trim().parse::<debversion::DebianVersionNum>().unwrap();
+                        self.installed_debvers.insert(current_package_num, debver);
+                    } else if key == "Section" {
+                        self.installed_sections.insert(current_package_num, value.to_string());
+                    } else if key == "Installed-Size" {
+                        if let Ok(size) = value.trim().parse::<u64>() {
+                            self.installed_disk_sizes.insert(current_package_num, size);
                         }
                     }
                 }
             }
         }
-        println!("Packages installed: {}", self.installed_debvers.keys().len());
+        return Ok(self.installed_debvers.keys().len());
     }
 
-    /// Loads packages, version numbers, dependencies, and md5sums from a file, calling get_package_num_inserting on the package name
-    /// and inserting the appropriate values into the dependencies, md5sum, and available_debvers maps.
-    pub fn parse_packages(&mut self, filename: &str) {
+    /// Loads packages, version numbers, dependencies (Depends and Pre-Depends), Recommends, Suggests, conflicts, sections,
+    /// priorities, Essential flags, sizes and md5sums from a file, calling get_package_num_inserting on the package name and
+    /// inserting the appropriate values into the dependencies, pre_dependencies, recommends, suggests, conflicts, md5sum,
+    /// sections, priorities, essential, download_sizes, available_disk_sizes and available_debvers maps.
+    /// Returns the number of available packages.
+    pub fn parse_packages(&mut self, filename: &str) -> io::Result<usize> {
         let kv_regexp = Regex::new(KEYVAL_REGEX).unwrap();
         let pkgver_regexp = Regex::new(PKGNAME_AND_VERSION_REGEX).unwrap();
 
-        if let Ok(lines) = read_lines(filename) {
-            let mut current_package_num = 0;
-            for line in lines {
-                if let Ok(ip) = line {
-                    // do more things with ip
-                    match kv_regexp.captures(&ip) {
-                        None => (),
-                        Some(caps) => {
-                            let (key, value) = (caps.name("key").unwrap().as_str(), caps.name("value").unwrap().as_str());
-                            if key == "Package" {
-                                current_package_num = self.get_package_num_inserting(&value);
-                            } else if key == "MD5sum" {
-                                self.md5sums.insert(current_package_num, value.to_string());
-                            } else if key == "Version" {
-                                let debver = value.trim().parse::<debversion::DebianVersionNum>().unwrap();
-                                self.available_debvers.insert(current_package_num, debver);
-                            } else if key == "Depends" {
-                                let mut curr_deps = Vec::new();
-                                for dep_group in value.split(',') {
-                                    let mut alternatives = Vec::new();
-                                    for dep in dep_group.split('|') {
-                                        if let Some(caps) = pkgver_regexp.captures(&dep.trim()) {
-                                            let (pkg, op, ver) = (caps.name("pkg").unwrap().as_str(), caps.name("op"), caps.name("ver"));
-                                            alternatives.push(RelVersionedPackageNum {
-                                                package_num: self.get_package_num_inserting(pkg),
-                                                rel_version: match op {
-                                                    None => None,
-                                                    Some(op) => Some((op.as_str().parse::<debversion::VersionRelation>().unwrap(), ver.unwrap().as_str().to_string()))
-                                                }
-                                            })
-                                        }
-                                    }
-                                    curr_deps.push(alternatives);
-                                }                
-
-                                self.dependencies.insert(current_package_num, curr_deps);
-                            }
+        let lines = read_lines(filename)?;
+        let mut current_package_num = 0;
+        for line in lines {
+            let ip = line?;
+            // do more things with ip
+            match kv_regexp.captures(&ip) {
+                None => (),
+                Some(caps) => {
+                    let (key, value) = (caps.name("key").unwrap().as_str(), caps.name("value").unwrap().as_str());
+                    if key == "Package" {
+                        current_package_num = self.get_package_num_inserting(value);
+                        // a previous load's stanza for this package goes away with its fields, even those this one lacks
+                        self.forget_available_fields(current_package_num);
+                    } else if key == "Section" {
+                        self.sections.insert(current_package_num, value.to_string());
+                    } else if key == "Size" {
+                        if let Ok(size) = value.trim().parse::<u64>() {
+                            self.download_sizes.insert(current_package_num, size);
                         }
+                    } else if key == "Installed-Size" {
+                        if let Ok(size) = value.trim().parse::<u64>() {
+                            self.available_disk_sizes.insert(current_package_num, size);
+                        }
+                    } else if key == "Priority" {
+                        self.priorities.insert(current_package_num, value.trim().to_string());
+                    } else if key == "Essential" {
+                        if value.trim() == "yes" {
+                            self.essential.insert(current_package_num);
+                        }
+                    } else if key == "MD5sum" {
+                        self.md5sums.insert(current_package_num, value.to_string());
+                    } else if key == "Version" {
+                        let debver = value.trim().parse::<debversion::DebianVersionNum>().unwrap();
+                        self.available_debvers.insert(current_package_num, debver);
+                    } else if key == "Depends" {
+                        let curr_deps = self.parse_relations(&pkgver_regexp, value);
+                        self.dependencies.insert(current_package_num, curr_deps);
+                    } else if key == "Pre-Depends" {
+                        let curr_deps = self.parse_relations(&pkgver_regexp, value);
+                        self.pre_dependencies.insert(current_package_num, curr_deps);
+                    } else if key == "Recommends" {
+                        let curr_deps = self.parse_relations(&pkgver_regexp, value);
+                        self.recommends.insert(current_package_num, curr_deps);
+                    } else if key == "Suggests" {
+                        let curr_deps = self.parse_relations(&pkgver_regexp, value);
+                        self.suggests.insert(current_package_num, curr_deps);
+                    } else if key == "Conflicts" || key == "Breaks" {
+                        let kind = if key == "Conflicts" { ConflictKind::Conflicts } else { ConflictKind::Breaks };
+                        // alternatives make no sense in these fields; every package named is a conflict
+                        let targets = self.parse_relations(&pkgver_regexp, value).into_iter().flatten();
+                        self.conflicts.entry(current_package_num).or_default().extend(targets.map(|target| Conflict { kind, target }));
+                    }
+                }
+            }
+        }
+        return Ok(self.available_debvers.keys().len());
+    }
+
+    // forgets what a Packages stanza said about package_num, except its version, which every stanza has
+    fn forget_available_fields(&mut self, package_num: i32) {
+        self.dependencies.insert(package_num, vec![]);
+        self.pre_dependencies.remove(&package_num);
+        self.recommends.remove(&package_num);
+        self.suggests.remove(&package_num);
+        self.conflicts.remove(&package_num);
+        self.essential.remove(&package_num);
+        self.sections.remove(&package_num);
+        self.priorities.remove(&package_num);
+        self.md5sums.remove(&package_num);
+        self.download_sizes.remove(&package_num);
+        self.available_disk_sizes.remove(&package_num);
+    }
+
+    /// Loads source packages from a Sources index: their versions, binary packages and build relationships
+    /// (Build-Depends, Build-Depends-Indep, Build-Depends-Arch and Build-Conflicts), calling get_package_num_inserting
+    /// on the binary package names they mention, into the sources map. Fields may be folded over several lines. If a
+    /// source package is listed more than once, the highest version counts.
+    /// Returns the number of source packages.
+    pub fn parse_sources(&mut self, filename: &str) -> io::Result<usize> {
+        let field_regexp = Regex::new(FIELD_REGEX).unwrap();
+        let regexps = [Regex::new(PKGNAME_AND_VERSION_REGEX).unwrap(), Regex::new(RESTRICTION_REGEX).unwrap(), Regex::new(ARCH_QUALIFIER_REGEX).unwrap()];
+
+        let mut lines = read_lines(filename)?;
+        let mut fields : Vec<(String, String)> = vec![];
+        loop {
+            let line = lines.next().transpose()?;
+            match &line {
+                Some(l) if l.starts_with(' ') || l.starts_with('\t') => {
+                    if let Some((_, value)) = fields.last_mut() {
+                        value.push(' ');
+                        value.push_str(l.trim());
+                    }
+                }
+                Some(l) if !l.trim().is_empty() => {
+                    if let Some(caps) = field_regexp.captures(l) {
+                        fields.push((caps.name("key").unwrap().as_str().to_string(), caps.name("value").unwrap().as_str().trim().to_string()));
+                    }
+                }
+                _ => {
+                    // a blank line or the end of the file ends a stanza
+                    self.add_source(&regexps, &fields);
+                    fields.clear();
+                    if line.is_none() { break; }
+                }
+            }
+        }
+        return Ok(self.sources.len());
+    }
+
+    // adds the source package of one Sources stanza, if it has a Package and a Version
+    fn add_source(&mut self, regexps: &[Regex; 3], fields: &[(String, String)]) {
+        let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, value)| &value[..]);
+        let (name, version) = match (field("Package"), field("Version")) {
+            (Some(name), Some(version)) => (name.to_string(), version.parse::<debversion::DebianVersionNum>().unwrap()),
+            _ => return
+        };
+        if self.sources.get(&name).is_some_and(|s| s.version > version) {
+            return;
+        }
+        let mut relations = vec![];
+        for build_field in BuildField::ALL.iter() {
+            if let Some(value) = field(&build_field.to_string()) {
+                for alternatives in self.parse_build_relations(regexps, value) {
+                    relations.push(BuildRelation { field : *build_field, alternatives });
+                }
+            }
+        }
+        let binaries = field("Binary").map_or(vec![], |b| b.split(',').map(|b| b.trim().to_string()).filter(|b| !b.is_empty()).collect());
+        if !self.sources.contains_key(&name) {
+            self.source_name_index.insert(&name, self.source_names.len() as i32);
+            self.source_names.push(name.clone());
+        }
+        self.sources.insert(name, SourcePackage { version, binaries, relations });
+    }
+
+    // parses a build relationship field such as Build-Depends: "a [amd64] <!nocheck> | b:any (>= 1), c" like
+    // parse_relations, keeping the architecture restrictions and dropping architecture qualifiers. Alternatives that
+    // only apply with some build profile are left out, since we build with none; so are clauses with none left.
+    fn parse_build_relations(&mut self, regexps: &[Regex; 3], value: &str) -> Vec<Vec<BuildAlternative>> {
+        let [pkgver_regexp, restriction_regexp, qualifier_regexp] = regexps;
+        let mut relations = Vec::new();
+        for dep_group in value.split(',') {
+            let mut alternatives = Vec::new();
+            for dep in dep_group.split('|') {
+                let mut architectures = vec![];
+                let mut profile_formulas = vec![];
+                for caps in restriction_regexp.captures_iter(dep) {
+                    if let Some(archs) = caps.name("archs") {
+                        architectures.extend(archs.as_str().split_whitespace().map(String::from));
                     }
+                    if let Some(profiles) = caps.name("profiles") {
+                        profile_formulas.push(profiles.as_str());
+                    }
+                }
+                // with no profile active, a formula holds if all its terms are negated; one of them has to hold
+                if !profile_formulas.is_empty() && !profile_formulas.iter().any(|f| f.split_whitespace().all(|term| term.starts_with('!'))) {
+                    continue;
+                }
+                let unrestricted = restriction_regexp.replace_all(dep, "");
+                let unqualified = qualifier_regexp.replace(unrestricted.trim(), "$pkg");
+                if let Some(caps) = pkgver_regexp.captures(unqualified.trim()) {
+                    let (pkg, op, ver) = (caps.name("pkg").unwrap().as_str(), caps.name("op"), caps.name("ver"));
+                    let dependency = RelVersionedPackageNum {
+                        package_num: self.get_package_num_inserting(pkg),
+                        rel_version: op.map(|op| (op.as_str().parse::<debversion::VersionRelation>().unwrap(), ver.unwrap().as_str().trim().to_string()))
+                    };
+                    alternatives.push(BuildAlternative { dependency, architectures });
+                }
+            }
+            if !alternatives.is_empty() {
+                relations.push(alternatives);
+            }
+        }
+        return relations;
+    }
+
+    // parses a relationship field such as Depends: "a (>= 1) | b, c" into one Dependency per comma-separated group
+    fn parse_relations(&mut self, pkgver_regexp: &Regex, value: &str) -> Vec<Dependency> {
+        let mut relations = Vec::new();
+        for dep_group in value.split(',') {
+            let mut alternatives = Vec::new();
+            for dep in dep_group.split('|') {
+                if let Some(caps) = pkgver_regexp.captures(dep.trim()) {
+                    let (pkg, op, ver) = (caps.name("pkg").unwrap().as_str(), caps.name("op"), caps.name("ver"));
+                    alternatives.push(RelVersionedPackageNum {
+                        package_num: self.get_package_num_inserting(pkg),
+                        rel_version: op.map(|op| (op.as_str().parse::<debversion::VersionRelation>().unwrap(), ver.unwrap().as_str().to_string()))
+                    })
                 }
             }
+            relations.push(alternatives);
         }
-        println!("Packages available: {}", self.available_debvers.keys().len());
+        return relations;
     }
 }
 
 
+/// One package of a how-to-install request, with an optional version constraint, written like a Depends entry:
+/// "pkg" or "pkg (>= 1.0)".
+pub struct InstallRequest {
+    pub package : String,
+    pub rel_version : Option<(debversion::VersionRelation, String)>,
+}
+
+impl FromStr for InstallRequest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pkgver_regexp = Regex::new(&format!("^{}$", PKGNAME_AND_VERSION_REGEX)).unwrap();
+        let caps = pkgver_regexp.captures(s.trim()).ok_or(format!("can't parse request {:?} (expected pkg or pkg (op version))", s))?;
+        let rel_version = match (caps.name("op"), caps.name("ver")) {
+            (Some(op), Some(ver)) => match op.as_str() {
+                "<<" | "<=" | "=" | ">=" | ">>" => Some((op.as_str().parse::<debversion::VersionRelation>().unwrap(), ver.as_str().to_string())),
+                op => return Err(format!("bad version relation {} in request {:?}", op, s))
+            },
+            _ => None
+        };
+        return Ok(InstallRequest { package : caps.name("pkg").unwrap().as_str().to_string(), rel_version });
+    }
+}
+
+impl fmt::Display for InstallRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match &self.rel_version {
+            None => write!(f, "{}", self.package),
+            Some((op, ver)) => write!(f, "{} ({} {})", self.package, op, ver)
+        }
+    }
+}
+
+/// Groups the words of a command line into requests: "a b (>= 1.0) c" is a, b (>= 1.0) and c.
+pub fn parse_install_requests(words: &[&str]) -> Result<Vec<InstallRequest>, String> {
+    let mut groups : Vec<String> = vec![];
+    let mut in_constraint = false;
+    for word in words.iter().filter(|w| !w.is_empty()) {
+        match groups.last_mut() {
+            Some(group) if in_constraint || word.starts_with('(') => { group.push(' '); group.push_str(word); }
+            _ => groups.push(word.to_string())
+        }
+        if word.starts_with('(') { in_constraint = true; }
+        if word.ends_with(')') { in_constraint = false; }
+    }
+    return groups.iter().map(|g| g.parse()).collect();
+}
+
+/// Reads requests from a file, one per line ("pkg" or "pkg (>= 1.0)"); blank lines and lines starting with # are skipped.
+pub fn read_install_requests(filename: &str) -> Result<Vec<InstallRequest>, String> {
+    let mut requests = vec![];
+    let lines = read_lines(filename).map_err(|e| format!("couldn't read {}: {}", filename, e))?;
+    for (n, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("couldn't read {}: {}", filename, e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        requests.push(line.parse().map_err(|e| format!("{}:{}: {}", filename, n + 1, e))?);
+    }
+    return Ok(requests);
+}
+
 // standard template code downloaded from the Internet somewhere
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 where P: AsRef<Path>, {
@@ -0,0 +1,134 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::Packages;
+use crate::debversion::DebianVersionNum;
+use crate::packages::Dependency;
+
+/// What a solver knows when it has to pick one alternative of a Depends clause.
+pub struct PolicyContext<'a, 'b> {
+    pub packages : &'a Packages,
+    /// the installed version of a package, as far as the solver is concerned
+    pub installed : &'b dyn Fn(i32) -> Option<&'a DebianVersionNum>,
+    /// whether the solver has already picked a package
+    pub selected : &'b dyn Fn(i32) -> bool,
+}
+
+/// How the solvers choose between the alternatives A | B | C of a Depends clause.
+pub trait AlternativePolicy {
+    /// Returns the alternatives of dep (as package numbers, each once), best first.
+    fn rank(&self, context: &PolicyContext, dep: &Dependency) -> Vec<i32>;
+}
+
+/// The alternatives in the order they're listed.
+pub struct FirstListed;
+
+/// Installed alternatives (at any version) first, then the rest, each in the order they're listed.
+pub struct PreferInstalled;
+
+/// Alternatives the solver has already picked first, then the rest, each in the order they're listed.
+pub struct PreferAlreadySelected;
+
+/// The alternative with the highest version in its constraint first (yes, comparing apples and oranges), then the
+/// unversioned ones in the order they're listed.
+pub struct HighestVersion;
+
+/// The alternative that brings in the fewest packages first: counting the packages in its transitive closure (along
+/// the first alternative of each clause) that are neither installed nor picked already.
+pub struct SmallestClosure;
+
+/// The names --policy accepts.
+pub const POLICY_NAMES : [&str; 5] = ["first-listed", "prefer-installed", "prefer-already-selected", "highest-version", "smallest-closure"];
+
+/// Looks up a built-in policy by name (see POLICY_NAMES).
+pub fn alternative_policy(name: &str) -> Result<Box<dyn AlternativePolicy>, String> {
+    return match name {
+        "first-listed" => Ok(Box::new(FirstListed)),
+        "prefer-installed" => Ok(Box::new(PreferInstalled)),
+        "prefer-already-selected" => Ok(Box::new(PreferAlreadySelected)),
+        "highest-version" => Ok(Box::new(HighestVersion)),
+        "smallest-closure" => Ok(Box::new(SmallestClosure)),
+        _ => Err(format!("unknown policy {} (expected one of {})", name, POLICY_NAMES.join(", ")))
+    };
+}
+
+// the alternatives of dep in the order they're listed, without repeats
+fn listed(dep: &Dependency) -> Vec<i32> {
+    let mut result = vec![];
+    for alternative in dep {
+        if !result.contains(&alternative.package_num) {
+            result.push(alternative.package_num);
+        }
+    }
+    return result;
+}
+
+impl AlternativePolicy for FirstListed {
+    fn rank(&self, _context: &PolicyContext, dep: &Dependency) -> Vec<i32> {
+        return listed(dep);
+    }
+}
+
+impl AlternativePolicy for PreferInstalled {
+    fn rank(&self, context: &PolicyContext, dep: &Dependency) -> Vec<i32> {
+        let mut alternatives = listed(dep);
+        alternatives.sort_by_key(|p| (context.installed)(*p).is_none());
+        return alternatives;
+    }
+}
+
+impl AlternativePolicy for PreferAlreadySelected {
+    fn rank(&self, context: &PolicyContext, dep: &Dependency) -> Vec<i32> {
+        let mut alternatives = listed(dep);
+        alternatives.sort_by_key(|p| !(context.selected)(*p));
+        return alternatives;
+    }
+}
+
+impl AlternativePolicy for HighestVersion {
+    fn rank(&self, _context: &PolicyContext, dep: &Dependency) -> Vec<i32> {
+        // an alternative listed twice counts with its highest version
+        let mut alternatives : Vec<(i32, Option<DebianVersionNum>)> = vec![];
+        for el in dep {
+            let version = el.rel_version.as_ref().map(|(_, ver)| ver.parse::<DebianVersionNum>().unwrap());
+            match alternatives.iter_mut().find(|(p, _)| *p == el.package_num) {
+                Some((_, v)) => if version > *v { *v = version },
+                None => alternatives.push((el.package_num, version))
+            }
+        }
+        alternatives.sort_by(|(_, v1), (_, v2)| v2.cmp(v1));
+        return alternatives.into_iter().map(|(p, _)| p).collect();
+    }
+}
+
+impl AlternativePolicy for SmallestClosure {
+    fn rank(&self, context: &PolicyContext, dep: &Dependency) -> Vec<i32> {
+        let mut alternatives : Vec<(i32, usize)> = listed(dep).into_iter().map(|p| (p, closure_size(context, p))).collect();
+        alternatives.sort_by_key(|(_, size)| *size);
+        return alternatives.into_iter().map(|(p, _)| p).collect();
+    }
+}
+
+// the number of packages that picking package_num would bring in, along the first alternative of each unsatisfied clause
+fn closure_size(context: &PolicyContext, package_num: i32) -> usize {
+    let present = |p: i32| (context.installed)(p).is_some() || (context.selected)(p);
+    if present(package_num) {
+        return 0;
+    }
+    let mut seen : HashSet<i32> = HashSet::new();
+    let mut worklist = VecDeque::new();
+    seen.insert(package_num);
+    worklist.push_back(package_num);
+    while let Some(p) = worklist.pop_front() {
+        for dep in context.packages.dependencies.get(&p).map_or(&[][..], |d| &d[..]) {
+            if context.packages.dep_is_satisfied_in(dep, context.installed).is_some() || dep.iter().any(|a| (context.selected)(a.package_num)) {
+                continue;
+            }
+            if let Some(first) = dep.first() {
+                if !present(first.package_num) && seen.insert(first.package_num) {
+                    worklist.push_back(first.package_num);
+                }
+            }
+        }
+    }
+    return seen.len();
+}
@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::debversion::DebianVersionNum;
+use crate::packages::{NoSuchPackage, Render};
+use crate::packages::deps_available::version_satisfies;
+
+impl Packages {
+    /// Returns, for each package, the Depends and Pre-Depends clauses of installed packages that mention it, as (package,
+    /// clause index), numbered as by install_clause. We only know the dependencies of the available version, so those
+    /// stand in for the installed version's.
+    fn installed_reverse_deps(&self) -> HashMap<i32, Vec<(i32, usize)>> {
+        let mut rdeps : HashMap<i32, Vec<(i32, usize)>> = HashMap::new();
+        for package_num in self.installed_debvers.keys() {
+            for (i, dep) in self.install_clauses(*package_num).enumerate() {
+                for alternative in dep {
+                    rdeps.entry(alternative.package_num).or_default().push((*package_num, i));
+                }
+            }
+        }
+        for clauses in rdeps.values_mut() {
+            clauses.sort();
+            clauses.dedup();
+        }
+        return rdeps;
+    }
+
+    /// Works out what removing package_names would do to the installed packages: the installed packages whose
+    /// dependencies would no longer be satisfied have to go too (transitively), dependencies that an alternative keeps
+    /// satisfied are pointed out, and installed packages that were only needed by removed ones are orphaned.
+    /// Dependencies that are already unsatisfied don't count.
+    pub fn plan_remove(&self, package_names: &[&str]) -> Result<RemovalImpact, NoSuchPackage> {
+        let mut impact = RemovalImpact {
+            packages : package_names.iter().map(|p| p.to_string()).collect(),
+            not_installed : vec![],
+            broken : vec![],
+            still_satisfied : vec![],
+            orphaned : vec![],
+        };
+        let mut removed : HashSet<i32> = HashSet::new();
+        let mut worklist : VecDeque<i32> = VecDeque::new();
+        for name in package_names {
+            let package_num = self.find_package_num(name).ok_or_else(|| self.no_such_package(name))?;
+            if !self.installed_debvers.contains_key(&package_num) {
+                impact.not_installed.push(name.to_string());
+            } else if removed.insert(package_num) {
+                worklist.push_back(package_num);
+            }
+        }
+
+        let rdeps = self.installed_reverse_deps();
+        let installed = |removed: &HashSet<i32>, p: i32| -> Option<&DebianVersionNum> {
+            if removed.contains(&p) { None } else { self.installed_debvers.get(&p) }
+        };
+        // clauses we've seen satisfied by an alternative, which may still break if that one goes too
+        let mut alternatives : Vec<(i32, usize, i32, i32)> = vec![];
+        while let Some(r) = worklist.pop_front() {
+            for (q, i) in rdeps.get(&r).map_or(&[][..], |c| &c[..]) {
+                if removed.contains(q) { continue; }
+                let (dep, _) = self.install_clause(*q, *i);
+                // only clauses that r satisfied before any removal can break because of it
+                let was_satisfied = dep.iter().any(|a| a.package_num == r && version_satisfies(&self.installed_debvers[&r], &a.rel_version));
+                if !was_satisfied { continue; }
+                match self.dep_is_satisfied_in(dep, |p| installed(&removed, p)) {
+                    Some(p) => alternatives.push((*q, *i, r, p)),
+                    None => {
+                        removed.insert(*q);
+                        worklist.push_back(*q);
+                        impact.broken.push(BrokenByRemoval {
+                            package : self.get_package_name(*q).to_string(),
+                            installed_version : self.installed_debvers[q].to_string(),
+                            dependency : self.dep2str(dep),
+                            pre_depends : *i >= self.dependencies[q].len(),
+                            removed : self.get_package_name(r).to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        let mut reported = HashSet::new();
+        for (q, i, r, p) in alternatives {
+            if removed.contains(&q) || !reported.insert((q, i)) { continue; }
+            // p itself may have been removed later on; report what satisfies the clause in the end
+            let (dep, _) = self.install_clause(q, i);
+            let p = self.dep_is_satisfied_in(dep, |x| installed(&removed, x)).unwrap_or(p);
+            impact.still_satisfied.push(SatisfiedByAlternative {
+                package : self.get_package_name(q).to_string(),
+                dependency : self.dep2str(dep),
+                pre_depends : i >= self.dependencies[&q].len(),
+                removed : self.get_package_name(r).to_string(),
+                satisfied_by : self.get_package_name(p).to_string(),
+            });
+        }
+
+        // an installed package is orphaned if something removed (or orphaned) depended on it, and nothing that stays does
+        let mut orphaned : HashSet<i32> = HashSet::new();
+        let mut candidates : VecDeque<i32> = removed.iter().copied().collect();
+        while let Some(g) = candidates.pop_front() {
+            for dep in self.install_clauses(g) {
+                for a in dep {
+                    let o = a.package_num;
+                    if removed.contains(&o) || orphaned.contains(&o) || !self.installed_debvers.contains_key(&o) { continue; }
+                    let needed = rdeps[&o].iter().any(|(q, _)| !removed.contains(q) && !orphaned.contains(q));
+                    if !needed {
+                        orphaned.insert(o);
+                        candidates.push_back(o);
+                        impact.orphaned.push(OrphanedPackage {
+                            package : self.get_package_name(o).to_string(),
+                            installed_version : self.installed_debvers[&o].to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        impact.still_satisfied.sort_by(|a, b| (&a.package, &a.dependency).cmp(&(&b.package, &b.dependency)));
+        impact.orphaned.sort_by(|a, b| a.package.cmp(&b.package));
+        return Ok(impact);
+    }
+}
+
+/// Result of the plan-remove query.
+#[derive(Serialize)]
+pub struct RemovalImpact {
+    /// the packages asked to be removed
+    pub packages : Vec<String>,
+    /// those of them that aren't installed
+    pub not_installed : Vec<String>,
+    /// installed packages that have to be removed too, in the order they were found
+    pub broken : Vec<BrokenByRemoval>,
+    pub still_satisfied : Vec<SatisfiedByAlternative>,
+    pub orphaned : Vec<OrphanedPackage>,
+}
+
+#[derive(Serialize)]
+pub struct BrokenByRemoval {
+    pub package : String,
+    pub installed_version : String,
+    /// the clause that would no longer be satisfied, whether it is a Pre-Depends, and the removed package that satisfied it
+    pub dependency : String,
+    pub pre_depends : bool,
+    pub removed : String,
+}
+
+#[derive(Serialize)]
+pub struct SatisfiedByAlternative {
+    pub package : String,
+    pub dependency : String,
+    pub pre_depends : bool,
+    pub removed : String,
+    pub satisfied_by : String,
+}
+
+/// An installed package that nothing needs once the removal is done.
+#[derive(Serialize)]
+pub struct OrphanedPackage {
+    pub package : String,
+    pub installed_version : String,
+}
+
+// how a clause is introduced, as in "bash pre-depends on ..."
+fn relation(pre_depends: bool) -> &'static str {
+    return if pre_depends { "pre-depends on" } else { "depends on" };
+}
+
+impl Render for RemovalImpact {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Removing: {}", self.packages.join(", "))?;
+        if !self.not_installed.is_empty() {
+            writeln!(out, "not installed: {}", self.not_installed.join(", "))?;
+        }
+        if !self.broken.is_empty() {
+            writeln!(out, "Also removed, since their dependencies would break:")?;
+            for b in &self.broken {
+                writeln!(out, "- {} {}: {} {:?} ({} is removed)", b.package, b.installed_version, relation(b.pre_depends), b.dependency, b.removed)?;
+            }
+        }
+        if !self.still_satisfied.is_empty() {
+            writeln!(out, "Still satisfied by an alternative:")?;
+            for s in &self.still_satisfied {
+                writeln!(out, "- {} {} {:?}: {} instead of {}", s.package, relation(s.pre_depends), s.dependency, s.satisfied_by, s.removed)?;
+            }
+        }
+        if !self.orphaned.is_empty() {
+            writeln!(out, "Orphaned (only needed by removed packages):")?;
+            for o in &self.orphaned {
+                writeln!(out, "- {} {}", o.package, o.installed_version)?;
+            }
+        }
+        return writeln!(out, "{} also removed, {} still satisfied by an alternative, {} orphaned",
+            self.broken.len(), self.still_satisfied.len(), self.orphaned.len());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::packages::SizeSummary;
+
+/// How the REPL prints query results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    Text,
+    /// one JSON document (on one line) per command
+    Json,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json")
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("unknown output format {} (expected text or json)", s))
+        }
+    }
+}
+
+/// A query result that can be rendered as the REPL's text output, or as a JSON document.
+/// Field names of the JSON documents are part of the interface; see the README for the schemas.
+pub trait Render: Serialize {
+    fn render(&self, out: &mut impl Write) -> io::Result<()>;
+
+    fn render_json(&self, out: &mut impl Write) -> io::Result<()> {
+        serde_json::to_writer(&mut *out, self)?;
+        return writeln!(out);
+    }
+
+    fn render_as(&self, format: OutputFormat, out: &mut impl Write) -> io::Result<()> {
+        return match format {
+            OutputFormat::Text => self.render(out),
+            OutputFormat::Json => self.render_json(out)
+        }
+    }
+}
+
+/// Returned by queries on a package name that isn't in the database.
+#[derive(Serialize, Debug)]
+#[serde(tag = "error", rename = "no_such_package")]
+pub struct NoSuchPackage {
+    pub package : String,
+    /// closest known names, best first
+    pub suggestions : Vec<String>,
+}
+
+impl Render for NoSuchPackage {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "no such package {}", self.package)?;
+        if !self.suggestions.is_empty() {
+            writeln!(out, "did you mean: {}?", self.suggestions.join(", "))?;
+        }
+        return Ok(());
+    }
+}
+
+/// A command that couldn't be carried out: bad syntax or arguments, or a file that can't be read or written.
+/// Rendered as the message itself, or as {"error": message} in JSON.
+#[derive(Serialize)]
+pub struct CommandError {
+    pub error : String,
+}
+
+impl CommandError {
+    pub fn new(message: impl Into<String>) -> CommandError {
+        return CommandError { error : message.into() };
+    }
+}
+
+impl Render for CommandError {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return writeln!(out, "{}", self.error);
+    }
+}
+
+/// Which kind of index a load command read.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexKind {
+    Available,
+    Installed,
+    Sources,
+}
+
+/// Result of loading an index: how many packages (or source packages) the database now has of that kind.
+#[derive(Serialize)]
+pub struct IndexLoaded {
+    pub file : String,
+    pub kind : IndexKind,
+    pub packages : usize,
+}
+
+impl Render for IndexLoaded {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return match self.kind {
+            IndexKind::Available => writeln!(out, "Packages available: {}", self.packages),
+            IndexKind::Installed => writeln!(out, "Packages installed: {}", self.packages),
+            IndexKind::Sources => writeln!(out, "Source packages: {}", self.packages)
+        }
+    }
+}
+
+/// Result of the info query. Available fields are None if the package isn't in the Packages index,
+/// installed_version is None if it isn't installed.
+#[derive(Serialize)]
+pub struct PackageInfo {
+    pub package : String,
+    pub version : Option<String>,
+    pub md5sum : Option<String>,
+    /// Depends clauses, formatted as in the Packages file
+    pub depends : Option<Vec<String>>,
+    pub installed_version : Option<String>,
+    /// Some(available > installed) if the package is both available and installed
+    pub newer_available : Option<bool>,
+}
+
+impl Render for PackageInfo {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Package: {}", self.package)?;
+        if let Some(version) = &self.version {
+            writeln!(out, "Version: {}", version)?;
+            writeln!(out, "MD5Sum: {}", self.md5sum.as_deref().unwrap_or(""))?;
+            writeln!(out, "Depends: {}", self.depends.as_deref().unwrap_or(&[]).join(", "))?;
+        }
+        if let Some(installed_version) = &self.installed_version {
+            writeln!(out, "Installed-Version: {}", installed_version)?;
+        }
+        if let Some(newer_available) = self.newer_available {
+            writeln!(out, "Newer-Available: {:?}", newer_available)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Result of the deps query: the package's Depends clauses, formatted as in the Packages file.
+#[derive(Serialize)]
+pub struct DependencyList {
+    pub package : String,
+    pub depends : Vec<String>,
+}
+
+impl Render for DependencyList {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return writeln!(out, "{:?} depends on {:?}", self.package, self.depends.join(", "));
+    }
+}
+
+/// Result of the transitive-dep-solution query.
+#[derive(Serialize)]
+pub struct TransitiveDepSolution {
+    pub package : String,
+    pub solution : Vec<String>,
+}
+
+impl Render for TransitiveDepSolution {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        return writeln!(out, "{:?} transitive dependency solution: {:?}", self.package, self.solution.join(", "));
+    }
+}
+
+/// Result of the how-to-install query: the packages to install, in the order they were chosen, and the installed packages
+/// that have to be removed because they conflict with them.
+#[derive(Serialize)]
+pub struct InstallPlan {
+    /// the requested packages, with their version constraints
+    pub packages : Vec<String>,
+    pub to_install : Vec<String>,
+    /// package to install -> the requested package it was chosen for
+    pub requested_by : BTreeMap<String, String>,
+    pub to_remove : Vec<PlannedRemoval>,
+    /// requests and dependencies that can't be satisfied, with the reason
+    pub unsatisfiable : Vec<String>,
+    /// requests with a version constraint that the installed version already satisfies
+    pub already_satisfied : Vec<String>,
+    /// the packages in to_install that a Recommends or Suggests clause asked for
+    pub soft_dependencies : Vec<SoftInstall>,
+    /// Recommends and Suggests clauses that nothing could be installed for, with the reasons
+    pub skipped : Vec<String>,
+    /// of installing to_install and removing to_remove
+    pub sizes : SizeSummary,
+}
+
+#[derive(Serialize)]
+pub struct SoftInstall {
+    pub package : String,
+    /// the package that recommends or suggests it
+    pub of : String,
+    pub relation : SoftRelation,
+    pub dependency : String,
+}
+
+#[derive(Serialize)]
+pub struct PlannedRemoval {
+    pub package : String,
+    pub installed_version : String,
+    pub reason : String,
+}
+
+impl Render for InstallPlan {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        let requested = self.packages.join(", ");
+        writeln!(out, "{} {}:", if self.packages.len() == 1 { "Package" } else { "Packages" }, requested)?;
+        writeln!(out, "{:?} to install: {:?}", requested, self.to_install.join(", "))?;
+        if self.packages.len() > 1 {
+            // which request brought each package in, in the order the packages are installed
+            let mut by_request : Vec<(&str, Vec<&str>)> = vec![];
+            for p in &self.to_install {
+                let request = &self.requested_by[p][..];
+                match by_request.iter_mut().find(|(r, _)| *r == request) {
+                    Some((_, packages)) => packages.push(p),
+                    None => by_request.push((request, vec![p]))
+                }
+            }
+            for (request, packages) in by_request {
+                writeln!(out, "- for {}: {}", request, packages.join(", "))?;
+            }
+        }
+        if !self.already_satisfied.is_empty() {
+            writeln!(out, "already satisfied: {}", self.already_satisfied.join(", "))?;
+        }
+        for s in &self.soft_dependencies {
+            writeln!(out, "- {}, as {} {} {:?}", s.package, s.of, s.relation, s.dependency)?;
+        }
+        for s in &self.skipped {
+            writeln!(out, "skipped: {}", s)?;
+        }
+        if !self.to_remove.is_empty() {
+            let names = self.to_remove.iter().map(|r| &r.package[..]).collect::<Vec<_>>();
+            writeln!(out, "{:?} to remove: {:?}", requested, names.join(", "))?;
+            for r in &self.to_remove {
+                writeln!(out, "- {} {} has to be removed: {}", r.package, r.installed_version, r.reason)?;
+            }
+        }
+        if !self.unsatisfiable.is_empty() {
+            writeln!(out, "{:?} can't be fully installed:", requested)?;
+            for u in &self.unsatisfiable {
+                writeln!(out, "- {}", u)?;
+            }
+        }
+        return self.sizes.render(out);
+    }
+}
+
+/// A relationship that install plans may follow besides Depends, like apt does.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftRelation {
+    Recommends,
+    Suggests,
+}
+
+impl fmt::Display for SoftRelation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", match self { SoftRelation::Recommends => "recommends", SoftRelation::Suggests => "suggests" });
+    }
+}
+
+#[derive(Serialize)]
+pub struct PlannedVersion {
+    pub package : String,
+    pub version : String,
+}
+
+#[derive(Serialize)]
+pub struct VersionChange {
+    pub package : String,
+    pub from : String,
+    pub to : String,
+}
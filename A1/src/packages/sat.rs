@@ -0,0 +1,322 @@
+// A small CDCL SAT solver: two watched literals, first-UIP clause learning, no restarts or clause deletion.
+// Problems built from package dependencies are small (the closure of a request), so we keep it simple.
+//
+// Decisions are driven by "guide" clauses rather than a variable order: the solver picks the first guide clause
+// whose negative literals are all false (e.g. a Depends clause of a package that is being installed) and that
+// isn't satisfied yet, and makes its first unassigned positive literal true. This mimics how the worklist
+// solvers pick the first alternative, but a bad pick is undone by conflict analysis instead of sticking.
+// Variables no guide clause asks for end up false.
+
+/// A literal: variable number times 2, plus 1 if negated.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Lit(usize);
+
+impl Lit {
+    pub fn positive(var: usize) -> Lit { Lit(var * 2) }
+    pub fn negative(var: usize) -> Lit { Lit(var * 2 + 1) }
+    pub fn var(self) -> usize { self.0 / 2 }
+    pub fn is_negative(self) -> bool { self.0 & 1 == 1 }
+    pub fn negated(self) -> Lit { Lit(self.0 ^ 1) }
+}
+
+fn lit_value(value: &[Option<bool>], l: Lit) -> Option<bool> {
+    return value[l.var()].map(|v| v != l.is_negative());
+}
+
+pub struct Solver {
+    num_vars : usize,
+    clauses : Vec<Vec<Lit>>,
+    // guide clauses in priority order, with their literals in the order they were added
+    // (the watched-literal scheme reorders the literals in `clauses`)
+    guides : Vec<Vec<Lit>>,
+    units : Vec<Lit>,
+    empty_clause : bool,
+    watches : Vec<Vec<usize>>,
+    value : Vec<Option<bool>>,
+    level : Vec<usize>,
+    reason : Vec<Option<usize>>,
+    trail : Vec<Lit>,
+    trail_lim : Vec<usize>,
+    qhead : usize,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            num_vars : 0,
+            clauses : vec![],
+            guides : vec![],
+            units : vec![],
+            empty_clause : false,
+            watches : vec![],
+            value : vec![],
+            level : vec![],
+            reason : vec![],
+            trail : vec![],
+            trail_lim : vec![],
+            qhead : 0,
+        }
+    }
+
+    pub fn new_var(&mut self) -> usize {
+        self.num_vars += 1;
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        self.value.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        return self.num_vars - 1;
+    }
+
+    /// Adds the clause l1 | l2 | ...; if guide is true, the clause also drives decisions (see above).
+    pub fn add_clause(&mut self, lits: &[Lit], guide: bool) {
+        let mut c : Vec<Lit> = vec![];
+        for l in lits {
+            if c.contains(&l.negated()) { return; } // tautology
+            if !c.contains(l) { c.push(*l); }
+        }
+        if guide {
+            self.guides.push(c.clone());
+        }
+        match c.len() {
+            0 => self.empty_clause = true,
+            1 => self.units.push(c[0]),
+            _ => {
+                let ci = self.clauses.len();
+                self.watches[c[0].0].push(ci);
+                self.watches[c[1].0].push(ci);
+                self.clauses.push(c);
+            }
+        }
+    }
+
+    /// Returns a satisfying assignment (indexed by variable), or None if the clauses are unsatisfiable.
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.empty_clause {
+            return None;
+        }
+        for l in self.units.clone() {
+            match lit_value(&self.value, l) {
+                Some(false) => return None,
+                Some(true) => (),
+                None => self.enqueue(l, None)
+            }
+        }
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.trail_lim.is_empty() {
+                    return None;
+                }
+                let (learnt, backtrack_level) = self.analyze(conflict);
+                self.cancel_until(backtrack_level);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let ci = self.clauses.len();
+                    self.watches[learnt[0].0].push(ci);
+                    self.watches[learnt[1].0].push(ci);
+                    let asserting = learnt[0];
+                    self.clauses.push(learnt);
+                    self.enqueue(asserting, Some(ci));
+                }
+            } else {
+                match self.pick_decision() {
+                    None => return Some(self.value.iter().map(|v| v.unwrap()).collect()),
+                    Some(l) => {
+                        self.trail_lim.push(self.trail.len());
+                        self.enqueue(l, None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn enqueue(&mut self, l: Lit, reason: Option<usize>) {
+        let v = l.var();
+        self.value[v] = Some(!l.is_negative());
+        self.level[v] = self.trail_lim.len();
+        self.reason[v] = reason;
+        self.trail.push(l);
+    }
+
+    // returns the index of a conflicting clause, if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_lit = self.trail[self.qhead].negated();
+            self.qhead += 1;
+            let mut ws = std::mem::take(&mut self.watches[false_lit.0]);
+            let mut i = 0;
+            while i < ws.len() {
+                let ci = ws[i];
+                let value = &self.value;
+                let c = &mut self.clauses[ci];
+                if c[0] == false_lit { c.swap(0, 1); }
+                if lit_value(value, c[0]) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                // find a new literal to watch instead of false_lit
+                if let Some(k) = (2..c.len()).find(|k| lit_value(value, c[*k]) != Some(false)) {
+                    c.swap(1, k);
+                    self.watches[c[1].0].push(ci);
+                    ws.swap_remove(i);
+                    continue;
+                }
+                let first = c[0];
+                if lit_value(value, first) == Some(false) {
+                    self.watches[false_lit.0] = ws;
+                    self.qhead = self.trail.len();
+                    return Some(ci);
+                }
+                self.enqueue(first, Some(ci));
+                i += 1;
+            }
+            self.watches[false_lit.0] = ws;
+        }
+        return None;
+    }
+
+    // first-UIP conflict analysis; returns the learnt clause (asserting literal first,
+    // a literal of the backtrack level second) and the level to backtrack to
+    fn analyze(&self, conflict: usize) -> (Vec<Lit>, usize) {
+        let current_level = self.trail_lim.len();
+        let mut seen = vec![false; self.num_vars];
+        let mut learnt = vec![Lit(0)];
+        let mut counter = 0;
+        let mut p : Option<Lit> = None;
+        let mut ci = conflict;
+        let mut idx = self.trail.len();
+        loop {
+            for q in &self.clauses[ci] {
+                if Some(*q) == p { continue; }
+                let v = q.var();
+                if !seen[v] && self.level[v] > 0 {
+                    seen[v] = true;
+                    if self.level[v] == current_level { counter += 1; } else { learnt.push(*q); }
+                }
+            }
+            loop {
+                idx -= 1;
+                if seen[self.trail[idx].var()] { break; }
+            }
+            let pl = self.trail[idx];
+            seen[pl.var()] = false;
+            counter -= 1;
+            if counter == 0 {
+                learnt[0] = pl.negated();
+                break;
+            }
+            p = Some(pl);
+            ci = self.reason[pl.var()].unwrap();
+        }
+
+        let mut backtrack_level = 0;
+        if learnt.len() > 1 {
+            let max_i = (1..learnt.len()).max_by_key(|i| self.level[learnt[*i].var()]).unwrap();
+            learnt.swap(1, max_i);
+            backtrack_level = self.level[learnt[1].var()];
+        }
+        return (learnt, backtrack_level);
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.trail_lim.len() <= level { return; }
+        let keep = self.trail_lim[level];
+        for l in self.trail.drain(keep..) {
+            self.value[l.var()] = None;
+            self.reason[l.var()] = None;
+        }
+        self.trail_lim.truncate(level);
+        self.qhead = self.trail.len();
+    }
+
+    fn pick_decision(&self) -> Option<Lit> {
+        for c in &self.guides {
+            let triggered = c.iter().filter(|l| l.is_negative()).all(|l| lit_value(&self.value, *l) == Some(false));
+            if !triggered || c.iter().any(|l| lit_value(&self.value, *l) == Some(true)) {
+                continue;
+            }
+            if let Some(l) = c.iter().find(|l| !l.is_negative() && lit_value(&self.value, **l).is_none()) {
+                return Some(*l);
+            }
+        }
+        return (0..self.num_vars).find(|v| self.value[*v].is_none()).map(Lit::negative);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a solver over n fresh variables with the given clauses, none of them guides unless listed in guides
+    fn solver(n: usize, clauses: &[&[Lit]], guides: &[&[Lit]]) -> Solver {
+        let mut solver = Solver::new();
+        for _ in 0..n {
+            solver.new_var();
+        }
+        for c in guides {
+            solver.add_clause(c, true);
+        }
+        for c in clauses {
+            solver.add_clause(c, false);
+        }
+        return solver;
+    }
+
+    fn satisfies(assignment: &[bool], clauses: &[&[Lit]]) -> bool {
+        return clauses.iter().all(|c| c.iter().any(|l| assignment[l.var()] != l.is_negative()));
+    }
+
+    fn pos(v: usize) -> Lit { Lit::positive(v) }
+    fn neg(v: usize) -> Lit { Lit::negative(v) }
+
+    #[test]
+    fn finds_a_satisfying_assignment() {
+        let clauses : &[&[Lit]] = &[&[pos(0), pos(1)], &[neg(0), pos(2)], &[neg(1), neg(2)], &[pos(3), neg(2)]];
+        let assignment = solver(4, clauses, &[]).solve().unwrap();
+        assert!(satisfies(&assignment, clauses));
+    }
+
+    #[test]
+    fn unguided_variables_are_false() {
+        let assignment = solver(3, &[&[neg(0), pos(1)]], &[&[pos(0)]]).solve().unwrap();
+        assert_eq!(assignment, vec![true, true, false]);
+    }
+
+    #[test]
+    fn detects_unsatisfiable_clauses() {
+        assert!(solver(1, &[&[pos(0)], &[neg(0)]], &[]).solve().is_none());
+        assert!(solver(1, &[&[]], &[]).solve().is_none());
+        // three pigeons, two holes: var 2 * pigeon + hole; only found by search, not by propagation alone
+        let mut clauses : Vec<Vec<Lit>> = (0..3).map(|p| vec![pos(2 * p), pos(2 * p + 1)]).collect();
+        for hole in 0..2 {
+            for p in 0..3 {
+                for q in p + 1..3 {
+                    clauses.push(vec![neg(2 * p + hole), neg(2 * q + hole)]);
+                }
+            }
+        }
+        let clauses : Vec<&[Lit]> = clauses.iter().map(|c| &c[..]).collect();
+        assert!(solver(6, &clauses, &[]).solve().is_none());
+    }
+
+    #[test]
+    fn backtracks_from_a_bad_guide_decision() {
+        // the guide picks a first, which forces c and d, which can't both hold; conflict analysis learns not-a
+        let clauses : &[&[Lit]] = &[&[neg(0), pos(2)], &[neg(0), pos(3)], &[neg(2), neg(3)]];
+        let assignment = solver(4, clauses, &[&[pos(0), pos(1)]]).solve().unwrap();
+        assert!(satisfies(&assignment, clauses));
+        assert_eq!(&assignment[..2], &[false, true]);
+    }
+
+    #[test]
+    fn backtracks_over_several_levels() {
+        // the first guide picks a, which rules out e and with it both b and c, which the second guide then needs; the
+        // solver has to undo a and take d
+        let clauses : &[&[Lit]] = &[&[neg(1), pos(4)], &[neg(2), pos(4)], &[neg(0), neg(4)]];
+        let guides : &[&[Lit]] = &[&[pos(0), pos(3)], &[neg(0), pos(1), pos(2)]];
+        let assignment = solver(5, clauses, guides).solve().unwrap();
+        assert!(satisfies(&assignment, clauses) && satisfies(&assignment, guides));
+        assert_eq!(assignment, vec![false, false, false, true, false]);
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::Render;
+
+impl Packages {
+    /// Adds up what carrying out a plan costs: the download size of the packages it installs (or upgrades), and the
+    /// change in disk space, with a per-package breakdown, biggest first. Installing a package takes its Installed-Size;
+    /// it replaces the installed version, if any, which gives back that one's size, as does removing a package. The
+    /// installed version's size comes from the installed packages file, or from the Packages index if that has the same
+    /// version. With empty_system, nothing counts as installed. Sizes that aren't known count as 0, and are listed.
+    pub fn size_summary(&self, install: &[i32], remove: &[i32], empty_system: bool) -> SizeSummary {
+        let replaced = |p: i32| if empty_system { Some(0) } else { self.replaced_disk_size(p) };
+        let mut packages : Vec<PackageSize> = vec![];
+        for p in install {
+            let disk_change = self.available_disk_sizes.get(p).copied().zip(replaced(*p)).map(|(new, old)| new as i64 - old as i64);
+            packages.push(PackageSize { package : self.get_package_name(*p).to_string(), download_bytes : self.download_sizes.get(p).copied(), disk_change_kib : disk_change });
+        }
+        for p in remove {
+            let disk_change = replaced(*p).map(|old| -(old as i64));
+            packages.push(PackageSize { package : self.get_package_name(*p).to_string(), download_bytes : Some(0), disk_change_kib : disk_change });
+        }
+        packages.sort_by(|a, b| b.disk_change_kib.cmp(&a.disk_change_kib)
+            .then_with(|| b.download_bytes.cmp(&a.download_bytes))
+            .then_with(|| a.package.cmp(&b.package)));
+        return SizeSummary {
+            download_bytes : packages.iter().filter_map(|p| p.download_bytes).sum(),
+            disk_change_kib : packages.iter().filter_map(|p| p.disk_change_kib).sum(),
+            unknown : packages.iter().filter(|p| p.download_bytes.is_none() || p.disk_change_kib.is_none()).map(|p| p.package.clone()).collect(),
+            packages,
+        };
+    }
+
+    // the Installed-Size of the installed version of package_num: 0 if it isn't installed, None if it isn't known
+    fn replaced_disk_size(&self, package_num: i32) -> Option<u64> {
+        let installed = match self.installed_debvers.get(&package_num) {
+            None => return Some(0),
+            Some(v) => v
+        };
+        if let Some(size) = self.installed_disk_sizes.get(&package_num) {
+            return Some(*size);
+        }
+        if self.available_debvers.get(&package_num) == Some(installed) {
+            return self.available_disk_sizes.get(&package_num).copied();
+        }
+        return None;
+    }
+}
+
+/// What carrying out a plan costs in downloads and disk space, with a per-package breakdown.
+#[derive(Serialize)]
+pub struct SizeSummary {
+    /// the total Size of the packages to install
+    pub download_bytes : u64,
+    /// the total change in disk space (Installed-Size), negative if space is freed
+    pub disk_change_kib : i64,
+    /// biggest disk space change first
+    pub packages : Vec<PackageSize>,
+    /// packages whose download or installed size isn't known (and counts as 0)
+    pub unknown : Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PackageSize {
+    pub package : String,
+    pub download_bytes : Option<u64>,
+    pub disk_change_kib : Option<i64>,
+}
+
+// a size in bytes, in kB, MB or GB like apt
+pub fn human_size(bytes: u64) -> String {
+    return match bytes {
+        0..=999 => format!("{} B", bytes),
+        1_000..=999_999 => format!("{:.1} kB", bytes as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.1} MB", bytes as f64 / 1e6),
+        _ => format!("{:.1} GB", bytes as f64 / 1e9)
+    }
+}
+
+fn human_disk_change(kib: i64) -> String {
+    return format!("{}{}", if kib < 0 { "-" } else { "+" }, human_size(kib.unsigned_abs() * 1024));
+}
+
+impl Render for SizeSummary {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.packages.is_empty() {
+            return Ok(());
+        }
+        writeln!(out, "download: {}, disk space: {}", human_size(self.download_bytes), human_disk_change(self.disk_change_kib))?;
+        let largest : Vec<String> = self.packages.iter()
+            .filter_map(|p| p.disk_change_kib.filter(|kib| *kib > 0).map(|kib| format!("{} ({})", p.package, human_disk_change(kib))))
+            .take(5)
+            .collect();
+        if !largest.is_empty() {
+            writeln!(out, "largest: {}", largest.join(", "))?;
+        }
+        if !self.unknown.is_empty() {
+            writeln!(out, "sizes unknown for: {}", self.unknown.join(", "))?;
+        }
+        return Ok(());
+    }
+}
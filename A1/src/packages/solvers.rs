@@ -1,15 +1,11 @@
-use crate::packages::Dependency;
 use crate::Packages;
 
-use super::RelVersionedPackageNum;
-use crate::debversion;
-use std::collections::VecDeque;
+use crate::debversion::DebianVersionNum;
+use crate::packages::{AlternativePolicy, ConflictIndex, ConflictRef, Dependency, FirstListed, HighestVersion, PolicyContext, SoftRelation};
+use crate::packages::deps_available::version_satisfies;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 impl Packages {
-    /// Computes a solution for the transitive dependencies of package_name; when there is a choice A | B | C,
-    /// chooses the first option A. Returns a Vec<i32> of package numbers.
-    ///
-    /// Note: does not consider which packages are installed.
     // pub fn transitive_dep_solution(&self, package_name: &str) -> Vec<i32> {
     //     if !self.package_exists(package_name) {
     //         return vec![];
@@ -51,124 +47,53 @@ impl Packages {
 
     //     return dependency_set;
     // }
+    /// Computes a solution for the transitive dependencies of package_name; when there is a choice A | B | C,
+    /// chooses the first option A. Returns a Vec<i32> of package numbers.
+    ///
+    /// Note: does not consider which packages are installed.
     pub fn transitive_dep_solution(&self, package_name: &str) -> Vec<i32> {
+        return self.transitive_dep_solution_with(package_name, &FirstListed);
+    }
+
+    /// Like transitive_dep_solution, but when there is a choice A | B | C, chooses the one that policy ranks first.
+    /// Only the policy looks at the installed packages.
+    pub fn transitive_dep_solution_with(&self, package_name: &str, policy: &dyn AlternativePolicy) -> Vec<i32> {
         if !self.package_exists(package_name) {
             return vec![];
         }
-    
+
         let mut dependency_set = vec![]; // Use Vec to maintain order
+        let mut in_set : HashSet<i32> = HashSet::new(); // and a set for membership, which is quadratic on the Vec
         let mut worklist = VecDeque::new();
-    
-        // Get initial dependencies and populate the worklist
-        if let Some(deps) = self.dependencies.get(&self.get_package_num(package_name)) {
-            for dep in deps {
-                if let Some(item) = dep.first() {
-                    if !dependency_set.contains(&item.package_num) {
-                        dependency_set.push(item.package_num);
-                        worklist.push_back(item.package_num);
-                    }
-                }
-            }
-        }
-    
+        worklist.push_back(*self.get_package_num(package_name));
+
         // Process the dependencies in the worklist
         while let Some(pkg_num) = worklist.pop_front() {
             if let Some(deps) = self.dependencies.get(&pkg_num) {
                 for dep in deps {
-                    if let Some(item) = dep.first() {
+                    let item = {
+                        let selected = |p: i32| in_set.contains(&p);
+                        let context = PolicyContext { packages : self, installed : &|p| self.installed_debvers.get(&p), selected : &selected };
+                        policy.rank(&context, dep).first().copied()
+                    };
+                    if let Some(item) = item {
                         // Add only if not already present
-                        if !dependency_set.contains(&item.package_num) {
-                            dependency_set.push(item.package_num);
-                            worklist.push_back(item.package_num);
+                        if in_set.insert(item) {
+                            dependency_set.push(item);
+                            worklist.push_back(item);
                         }
                     }
                 }
             }
         }
-    
+
         dependency_set
     }
-      
+
     /// Computes a set of packages that need to be installed to satisfy package_name's deps given the current installed packages.
-    /// When a dependency A | B | C is unsatisfied, there are two possible cases:
-    ///   (1) there are no versions of A, B, or C installed; pick the alternative with the highest version number (yes, compare apples and oranges).
-    ///   (2) at least one of A, B, or C is installed (say A, B), but with the wrong version; of the installed packages (A, B), pick the one with the highest version number.
+    /// Returns the install list of compute_install_plan, which also says what has to be removed.
     pub fn compute_how_to_install(&self, package_name: &str) -> Vec<i32> {
-        if !self.package_exists(package_name) {
-            return vec![];
-        }
-
-        let mut dependencies_to_add: Vec<i32> = vec![];
-
-        let deps = self
-            .dependencies
-            .get(&self.get_package_num(package_name))
-            .unwrap();
-        let mut remaining_deps = VecDeque::new();
-        remaining_deps.extend(deps.iter());
-
-        while let Some(current_dependency) = remaining_deps.pop_front() {
-            if self.dep_is_satisfied(current_dependency).is_some() {
-                continue;
-            }
-
-            let wrong_version_packages = self.dep_satisfied_by_wrong_version(current_dependency);
-
-            if wrong_version_packages.is_empty() {
-                if current_dependency.len() == 1 {
-                    let dep_num = current_dependency[0].package_num;
-                    if !dependencies_to_add.contains(&dep_num) {
-                        dependencies_to_add.push(dep_num);
-                        self.dependencies
-                            .get(&dep_num)
-                            .unwrap()
-                            .iter()
-                            .for_each(|md| remaining_deps.push_back(md));
-                    }
-                } else {
-                    let best_dep = current_dependency
-                        .iter()
-                        .map(|el| {
-                            let ver = el
-                                .rel_version
-                                .as_ref()
-                                .unwrap()
-                                .1
-                                .parse::<debversion::DebianVersionNum>()
-                                .unwrap();
-                            (el, ver)
-                        })
-                        .max_by(|(_, v1), (_, v2)| v1.cmp(v2))
-                        .unwrap();
-
-                    if !dependencies_to_add.contains(&best_dep.0.package_num) {
-                        dependencies_to_add.push(best_dep.0.package_num);
-                        self.dependencies
-                            .get(&best_dep.0.package_num)
-                            .unwrap()
-                            .iter()
-                            .for_each(|md| remaining_deps.push_back(md));
-                    }
-                }
-            } else {
-                let dep_num = wrong_version_packages
-                    .iter()
-                    .map(|name| self.get_package_num(name))
-                    .max_by(|&a, &b| a.cmp(&b))
-                    .unwrap();
-
-                if !dependencies_to_add.contains(&dep_num) {
-                    dependencies_to_add.push(*dep_num);
-                    self.dependencies
-                        .get(&dep_num)
-                        .unwrap()
-                        .iter()
-                        .for_each(|md| remaining_deps.push_back(md));
-                }
-            }
-        }
-
-        dependencies_to_add
+        return self.compute_install_plan(package_name).install;
     }
     // pub fn compute_how_to_install(&self, package_name: &str) -> Vec<i32> {
     //     if !self.package_exists(package_name) {
@@ -253,5 +178,521 @@ impl Packages {
     
     //     dependencies_to_add
     // }
-    
+
+    /// Computes the packages that need to be installed to satisfy package_name's deps (Depends and Pre-Depends) given the current
+    /// installed packages, and the installed packages that have to be removed because they conflict with (Conflicts or Breaks)
+    /// something being installed.
+    /// When a dependency A | B | C is unsatisfied, the alternatives whose available version satisfies it are ranked as follows:
+    ///   (1) there are no versions of A, B, or C installed; the alternative with the highest version number first (yes, compare apples and oranges),
+    ///       then the unversioned ones (see HighestVersion).
+    ///   (2) at least one of A, B, or C is installed (say A, B), but with the wrong version; only the installed packages (A, B), ranked the same way.
+    /// We pick the best alternative that doesn't conflict with a package being installed and doesn't require removing anything; failing that,
+    /// the best one that doesn't conflict with a package being installed, removing what it conflicts with. If there is none, the dependency
+    /// is reported as unsatisfiable. Dependencies that were satisfied by a package that gets removed are looked at again.
+    /// Dependencies with alternatives are put off until those without are resolved, so that a choice can take the
+    /// forced packages' conflicts into account; still, a choice is never revisited (solve_install does that).
+    pub fn compute_install_plan(&self, package_name: &str) -> HowToInstall {
+        return self.compute_install_plan_with(package_name, &HighestVersion);
+    }
+
+    /// Like compute_install_plan, but policy ranks the alternatives, after the installed ones with the wrong version
+    /// (which compute_install_plan ranks with HighestVersion).
+    pub fn compute_install_plan_with(&self, package_name: &str, policy: &dyn AlternativePolicy) -> HowToInstall {
+        return match self.find_package_num(package_name) {
+            None => HowToInstall::new(),
+            Some(root) => self.compute_install_plan_for(&[root], policy, SoftDependencies::default())
+        };
+    }
+
+    /// Like compute_install_plan_with, for several requested packages at once: one plan that installs all of them.
+    /// Each package to install is attributed to the request it was first chosen for. The plan then follows the soft
+    /// dependencies that soft asks for (see Planner::resolve_soft).
+    pub fn compute_install_plan_for(&self, package_nums: &[i32], policy: &dyn AlternativePolicy, soft: SoftDependencies) -> HowToInstall {
+        let mut planner = Planner::with_policy(self, policy);
+        for p in package_nums {
+            planner.select(*p, None);
+        }
+        planner.run();
+        planner.resolve_soft(soft);
+        let mut plan = planner.plan;
+        plan.install.retain(|p| !package_nums.contains(p));
+        return plan;
+    }
+}
+
+/// Which soft relationships compute_install_plan_for follows besides Depends; apt follows Recommends by default.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SoftDependencies {
+    pub recommends : bool,
+    pub suggests : bool,
+}
+
+/// Result of compute_install_plan, as package numbers.
+pub struct HowToInstall {
+    /// packages to install (or upgrade), in the order they were chosen
+    pub install : Vec<i32>,
+    /// package to install -> the requested package it was chosen for
+    pub requested_by : HashMap<i32, i32>,
+    pub remove : Vec<Removal>,
+    pub unsatisfiable : Vec<Unsatisfiable>,
+    /// packages chosen for a Recommends or Suggests clause, in the order they were chosen
+    pub soft : Vec<SoftChoice>,
+    /// Recommends and Suggests clauses that were left unsatisfied
+    pub skipped : Vec<SkippedSoft>,
+}
+
+impl HowToInstall {
+    fn new() -> HowToInstall {
+        return HowToInstall { install : vec![], requested_by : HashMap::new(), remove : vec![], unsatisfiable : vec![], soft : vec![], skipped : vec![] };
+    }
+}
+
+/// An installed package that compute_install_plan removes.
+#[derive(Clone)]
+pub struct Removal {
+    pub package_num : i32,
+    /// the package being installed that it conflicts with, and the conflict
+    pub installing : i32,
+    pub conflict : ConflictRef,
+    /// the Depends or Pre-Depends clause (package, index) whose alternatives all required a removal; None if the requested package
+    /// itself conflicts with it
+    pub needed_for : Option<(i32, usize)>,
+}
+
+/// A Depends or Pre-Depends clause (package, index) that compute_install_plan couldn't satisfy. A package's clauses are
+/// numbered from its Depends on, then its Pre-Depends.
+pub struct Unsatisfiable {
+    pub package_num : i32,
+    pub dep_index : usize,
+    /// why its alternatives were ruled out: conflicts with packages being installed
+    pub conflicts : Vec<ConflictRef>,
+    /// and alternatives whose available version doesn't satisfy it
+    pub wrong_versions : Vec<i32>,
+}
+
+/// A package that the plan installs for a Recommends or Suggests clause (package, relation, index).
+pub struct SoftChoice {
+    pub package_num : i32,
+    pub for_package : i32,
+    pub relation : SoftRelation,
+    pub dep_index : usize,
+}
+
+/// A Recommends or Suggests clause (package, relation, index) that none of whose alternatives could be installed.
+pub struct SkippedSoft {
+    pub package_num : i32,
+    pub relation : SoftRelation,
+    pub dep_index : usize,
+    /// why not, for each alternative
+    pub reasons : Vec<String>,
+}
+
+// a change that select() and run() make besides growing the plan's lists, to take back when a soft dependency doesn't
+// work out
+enum Undo {
+    // the package was selected (with its chosen_for, soft_for and requested_by entries)
+    Selected(i32),
+    Removed(i32),
+    // a clause was pushed onto the package's satisfied_via
+    SatisfiedVia(i32),
+    // the package's satisfied_via was taken, as it was
+    TookSatisfiedVia(i32, Vec<(i32, usize)>),
+}
+
+/// The greedy worklist behind compute_install_plan: select() the requested packages, then run().
+pub struct Planner<'a> {
+    packages : &'a Packages,
+    policy : &'a dyn AlternativePolicy,
+    index : ConflictIndex,
+    // plan for a system with nothing installed (see on_empty_system)
+    empty_system : bool,
+    selected : HashSet<i32>,
+    // selected package -> the Depends or Pre-Depends clause it was chosen for (None for the requested packages)
+    chosen_for : HashMap<i32, Option<(i32, usize)>>,
+    // selected package -> the package whose Recommends or Suggests it was chosen for
+    soft_for : HashMap<i32, i32>,
+    removed : HashSet<i32>,
+    // installed package -> the clauses it was taken to satisfy
+    satisfied_via : HashMap<i32, Vec<(i32, usize)>>,
+    // Depends and Pre-Depends clauses to resolve; those with alternatives go to choices, and wait until worklist is empty
+    worklist : VecDeque<(i32, usize)>,
+    choices : VecDeque<(i32, usize)>,
+    plan : HowToInstall,
+    // the changes to take back, while try_soft is trying an alternative
+    undo_log : Option<Vec<Undo>>,
+    // what can_install found out so far
+    installability : HashMap<i32, bool>,
+}
+
+impl<'a> Planner<'a> {
+    pub fn new(packages: &'a Packages) -> Planner<'a> {
+        return Planner::with_policy(packages, &HighestVersion);
+    }
+
+    /// A planner that ranks alternatives with policy (after the installed ones with the wrong version).
+    pub fn with_policy(packages: &'a Packages, policy: &'a dyn AlternativePolicy) -> Planner<'a> {
+        return Planner {
+            packages,
+            policy,
+            index : packages.conflict_index(),
+            empty_system : false,
+            selected : HashSet::new(),
+            chosen_for : HashMap::new(),
+            soft_for : HashMap::new(),
+            removed : HashSet::new(),
+            satisfied_via : HashMap::new(),
+            worklist : VecDeque::new(),
+            choices : VecDeque::new(),
+            plan : HowToInstall::new(),
+            undo_log : None,
+            installability : HashMap::new(),
+        };
+    }
+
+    /// A planner that ignores the installed packages, as if installing into an empty system.
+    pub fn on_empty_system(packages: &'a Packages, policy: &'a dyn AlternativePolicy) -> Planner<'a> {
+        let mut planner = Planner::with_policy(packages, policy);
+        planner.empty_system = true;
+        return planner;
+    }
+
+    // installed version of package_num, unless we're removing it
+    fn installed(&self, package_num: i32) -> Option<&'a DebianVersionNum> {
+        if self.empty_system || self.removed.contains(&package_num) {
+            return None;
+        }
+        return self.packages.installed_debvers.get(&package_num);
+    }
+
+    /// The version of package_num that the plan ends up with: the available one if it's selected, else the installed
+    /// one unless it's removed.
+    pub fn planned(&self, package_num: i32) -> Option<&'a DebianVersionNum> {
+        if self.selected.contains(&package_num) {
+            return self.packages.available_debvers.get(&package_num);
+        }
+        return self.installed(package_num);
+    }
+
+    /// The plan so far.
+    pub fn plan(&self) -> &HowToInstall {
+        return &self.plan;
+    }
+
+    /// Follows the Depends and Pre-Depends (and Recommends or Suggests) clauses that selected packages were chosen for back to a
+    /// requested package.
+    pub fn requested_for(&self, package_num: i32) -> i32 {
+        let mut p = package_num;
+        loop {
+            p = match (self.chosen_for.get(&p), self.soft_for.get(&p)) {
+                (Some(Some((owner, _))), _) => *owner,
+                (_, Some(owner)) => *owner,
+                _ => return p
+            };
+        }
+    }
+
+    /// Makes the plan keep package_num's currently satisfied dependencies satisfied: if a removal takes away what
+    /// satisfies one, it is resolved again like the dependencies of the selected packages.
+    pub fn keep_satisfied(&mut self, package_num: i32) {
+        for i in 0..self.packages.install_clause_count(package_num) {
+            let (dep, _) = self.packages.install_clause(package_num, i);
+            if let Some(p) = self.packages.dep_is_satisfied_in(dep, |p| self.planned(p)) {
+                if !self.selected.contains(&p) {
+                    self.satisfied_via.entry(p).or_default().push((package_num, i));
+                }
+            }
+        }
+    }
+
+    /// Satisfies dep as if a requested package depended on it, unless the plan already does: selects its best
+    /// alternative whose available version satisfies it and that doesn't conflict with a package being installed,
+    /// preferring one whose own dependencies can be met and then one that doesn't require a removal, and queues its
+    /// dependencies (for run()). Returns the package that
+    /// satisfies dep, or the conflicts that ruled out the alternatives if there is none.
+    pub fn require(&mut self, dep: &Dependency) -> Result<i32, Vec<ConflictRef>> {
+        if let Some(p) = self.packages.dep_is_satisfied_in(dep, |p| self.planned(p)) {
+            return Ok(p);
+        }
+        let mut conflicts = vec![];
+        let mut installable = vec![];
+        for c in self.candidates(dep) {
+            if !self.packages.available_debvers.contains_key(&c) {
+                continue;
+            }
+            let with_selected = self.conflicts_with_selected(c);
+            if with_selected.is_empty() {
+                installable.push(c);
+            } else {
+                conflicts.extend(with_selected.into_iter().map(|(_, conflict)| conflict));
+            }
+        }
+        return match self.choose(&installable) {
+            Some(c) => {
+                self.select(c, None);
+                Ok(c)
+            }
+            None => Err(conflicts)
+        };
+    }
+
+    /// Resolves the queued dependencies of the selected packages.
+    pub fn run(&mut self) {
+        while let Some((package_num, i)) = self.worklist.pop_front().or_else(|| self.choices.pop_front()) {
+            self.resolve(package_num, i);
+        }
+    }
+
+    // installed version of package_num, if it stays as it is
+    fn kept(&self, package_num: i32) -> Option<&'a DebianVersionNum> {
+        if self.selected.contains(&package_num) {
+            return None;
+        }
+        return self.installed(package_num);
+    }
+
+    fn conflicts(&self, package_num: i32, version_of: impl Fn(i32) -> Option<&'a DebianVersionNum>) -> Vec<(i32, ConflictRef)> {
+        return match self.packages.available_debvers.get(&package_num) {
+            None => vec![],
+            Some(version) => self.packages.conflicts_between(&self.index, package_num, version, version_of)
+        }
+    }
+
+    fn conflicts_with_selected(&self, package_num: i32) -> Vec<(i32, ConflictRef)> {
+        let available = &self.packages.available_debvers;
+        return self.conflicts(package_num, |p| if self.selected.contains(&p) { available.get(&p) } else { None });
+    }
+
+    /// Adds package_num to the packages to install (or upgrade), removing the installed packages it conflicts with,
+    /// and queues its Depends and Pre-Depends. needed_for is the clause it's chosen for; None for a requested package.
+    pub fn select(&mut self, package_num: i32, needed_for: Option<(i32, usize)>) {
+        if !self.selected.insert(package_num) {
+            return;
+        }
+        self.log(Undo::Selected(package_num));
+        self.chosen_for.insert(package_num, needed_for);
+        self.plan.install.push(package_num);
+        self.plan.requested_by.insert(package_num, self.requested_for(package_num));
+        for (other, conflict) in self.conflicts(package_num, |p| self.kept(p)) {
+            if self.removed.insert(other) {
+                self.log(Undo::Removed(other));
+                self.plan.remove.push(Removal { package_num : other, installing : package_num, conflict, needed_for });
+                if let Some(via) = self.satisfied_via.remove(&other) {
+                    for (p, i) in &via {
+                        self.queue(*p, *i);
+                    }
+                    self.log(Undo::TookSatisfiedVia(other, via));
+                }
+            }
+        }
+        for i in 0..self.packages.install_clause_count(package_num) {
+            self.queue(package_num, i);
+        }
+    }
+
+    fn queue(&mut self, package_num: i32, i: usize) {
+        if self.packages.install_clause(package_num, i).0.len() > 1 {
+            self.choices.push_back((package_num, i));
+        } else {
+            self.worklist.push_back((package_num, i));
+        }
+    }
+
+    // the alternatives of dep, best first by policy
+    fn ranked(&self, dep: &Dependency) -> Vec<i32> {
+        let installed = |p| self.installed(p);
+        let selected = |p| self.selected.contains(&p);
+        return self.policy.rank(&PolicyContext { packages : self.packages, installed : &installed, selected : &selected }, dep);
+    }
+
+    // the alternatives of dep that installing would satisfy it with, best first (see compute_install_plan): those whose
+    // available version satisfies it, and of those, the installed ones with the wrong version if there are any
+    fn candidates(&self, dep: &Dependency) -> Vec<i32> {
+        let mut ranked = self.ranked(dep);
+        ranked.retain(|p| !self.packages.wrong_available_version(dep, *p));
+        let wrong_version = self.packages.dep_satisfied_by_wrong_version_in(dep, |p| self.installed(p));
+        if ranked.iter().any(|p| wrong_version.contains(p)) {
+            ranked.retain(|p| wrong_version.contains(p));
+        }
+        return ranked;
+    }
+
+    // the alternative to select out of installable (best first): of those that can_install, or if none can, of all of
+    // them, the first that doesn't require a removal, else the first
+    fn choose(&mut self, installable: &[i32]) -> Option<i32> {
+        let viable : Vec<i32> = installable.iter().copied().filter(|c| self.can_install(*c)).collect();
+        let from = if viable.is_empty() { installable } else { &viable[..] };
+        return from.iter().find(|c| self.conflicts(**c, |p| self.kept(p)).is_empty()).or(from.first()).copied();
+    }
+
+    // whether package_num is available and each of its Depends and Pre-Depends clauses is satisfied by an installed
+    // package or has an alternative whose available version satisfies it and that can_install in turn; conflicts
+    // aren't considered, and a package on a dependency cycle counts as installable while the cycle is being checked
+    fn can_install(&mut self, package_num: i32) -> bool {
+        if let Some(known) = self.installability.get(&package_num) {
+            return *known;
+        }
+        let packages = self.packages;
+        if !packages.available_debvers.contains_key(&package_num) {
+            self.installability.insert(package_num, false);
+            return false;
+        }
+        self.installability.insert(package_num, true);
+        let empty_system = self.empty_system;
+        let installed = |p| if empty_system { None } else { packages.installed_debvers.get(&p) };
+        let result = packages.install_clauses(package_num).all(|dep| {
+            packages.dep_is_satisfied_in(dep, installed).is_some()
+                || dep.iter().any(|a| packages.available_debvers.contains_key(&a.package_num)
+                    && !packages.wrong_available_version(dep, a.package_num) && self.can_install(a.package_num))
+        });
+        self.installability.insert(package_num, result);
+        return result;
+    }
+
+    fn resolve(&mut self, package_num: i32, i: usize) {
+        if self.removed.contains(&package_num) {
+            return;
+        }
+        let (dep, _) = self.packages.install_clause(package_num, i);
+        if let Some(p) = self.packages.dep_is_satisfied_in(dep, |p| self.planned(p)) {
+            if !self.selected.contains(&p) {
+                self.satisfied_via.entry(p).or_default().push((package_num, i));
+                self.log(Undo::SatisfiedVia(p));
+            }
+            return;
+        }
+
+        let mut conflicts = vec![];
+        let mut installable = vec![];
+        for c in self.candidates(dep) {
+            if !self.packages.available_debvers.contains_key(&c) {
+                continue;
+            }
+            let with_selected = self.conflicts_with_selected(c);
+            if with_selected.is_empty() {
+                installable.push(c);
+            } else {
+                conflicts.extend(with_selected.into_iter().map(|(_, conflict)| conflict));
+            }
+        }
+        match self.choose(&installable) {
+            Some(c) => self.select(c, Some((package_num, i))),
+            None => {
+                let wrong_versions = self.ranked(dep).into_iter().filter(|p| self.packages.wrong_available_version(dep, *p)).collect();
+                self.plan.unsatisfiable.push(Unsatisfiable { package_num, dep_index : i, conflicts, wrong_versions })
+            }
+        }
+    }
+
+    /// Resolves the Recommends (and Suggests) clauses that soft asks for, of the planned packages and of the ones they
+    /// pull in, after run(). A clause is satisfied with its best alternative that can be installed along with its
+    /// dependencies without removing anything, leaving a dependency unsatisfied or needing a package that isn't
+    /// available; if there is none, it is skipped, with the reasons.
+    pub fn resolve_soft(&mut self, soft: SoftDependencies) {
+        let relations : Vec<SoftRelation> = [(soft.recommends, SoftRelation::Recommends), (soft.suggests, SoftRelation::Suggests)]
+            .iter().filter(|(wanted, _)| *wanted).map(|(_, relation)| *relation).collect();
+        if relations.is_empty() {
+            return;
+        }
+        // plan.install grows as soft dependencies are added, and theirs are resolved in turn
+        let mut next = 0;
+        while next < self.plan.install.len() {
+            let package_num = self.plan.install[next];
+            next += 1;
+            for &relation in &relations {
+                for i in 0..self.packages.soft_dependencies(package_num, relation).len() {
+                    self.resolve_soft_clause(package_num, relation, i);
+                }
+            }
+        }
+    }
+
+    fn resolve_soft_clause(&mut self, package_num: i32, relation: SoftRelation, i: usize) {
+        let packages = self.packages;
+        let dep = &packages.soft_dependencies(package_num, relation)[i];
+        if packages.dep_is_satisfied_in(dep, |p| self.planned(p)).is_some() || dep.iter().any(|a| self.selected.contains(&a.package_num)) {
+            return;
+        }
+        let mut reasons = vec![];
+        for c in self.ranked(dep) {
+            match self.try_soft(c, dep, package_num) {
+                Ok(()) => {
+                    self.plan.soft.push(SoftChoice { package_num : c, for_package : package_num, relation, dep_index : i });
+                    return;
+                }
+                Err(reason) => reasons.push(reason)
+            }
+        }
+        self.plan.skipped.push(SkippedSoft { package_num, relation, dep_index : i, reasons });
+    }
+
+    // selects c for the soft dependency dep of for_package and runs, if that goes through cleanly; otherwise leaves
+    // the plan as it was and says why not
+    fn try_soft(&mut self, c: i32, dep: &Dependency, for_package: i32) -> Result<(), String> {
+        let packages = self.packages;
+        let name = packages.get_package_name(c);
+        match packages.available_debvers.get(&c) {
+            None => return Err(format!("{} is not available", name)),
+            Some(v) if !dep.iter().any(|a| a.package_num == c && version_satisfies(v, &a.rel_version)) =>
+                return Err(format!("{} {} is available, which doesn't satisfy it", name, v)),
+            Some(_) => ()
+        }
+        let conflicts = self.conflicts_with_selected(c);
+        if !conflicts.is_empty() {
+            return Err(conflicts.iter().map(|(_, conflict)| packages.conflict2str(*conflict)).collect::<Vec<_>>().join("; "));
+        }
+
+        // the plan's lists only grow; the rest of what changes goes in the undo log
+        let (install, remove, unsatisfiable) = (self.plan.install.len(), self.plan.remove.len(), self.plan.unsatisfiable.len());
+        self.undo_log = Some(vec![]);
+        self.soft_for.insert(c, for_package);
+        self.select(c, None);
+        self.run();
+        let log = self.undo_log.take().unwrap();
+        let problem = if let Some(r) = self.plan.remove.get(remove) {
+            Some(format!("installing {} would remove {} ({})", name, packages.get_package_name(r.package_num), packages.conflict2str(r.conflict)))
+        } else if let Some(u) = self.plan.unsatisfiable.get(unsatisfiable) {
+            Some(format!("installing {} would leave a dependency unsatisfied: {}", name, packages.explain_unsatisfiable(u)))
+        } else {
+            self.plan.install[install..].iter().find(|p| !packages.available_debvers.contains_key(p))
+                .map(|p| format!("{} needs {}, which is not available", name, packages.get_package_name(*p)))
+        };
+        return match problem {
+            None => Ok(()),
+            Some(problem) => {
+                for undo in log.into_iter().rev() {
+                    self.undo(undo);
+                }
+                self.plan.install.truncate(install);
+                self.plan.remove.truncate(remove);
+                self.plan.unsatisfiable.truncate(unsatisfiable);
+                Err(problem)
+            }
+        };
+    }
+
+    fn log(&mut self, undo: Undo) {
+        if let Some(log) = &mut self.undo_log {
+            log.push(undo);
+        }
+    }
+
+    fn undo(&mut self, undo: Undo) {
+        match undo {
+            Undo::Selected(p) => {
+                self.selected.remove(&p);
+                self.chosen_for.remove(&p);
+                self.soft_for.remove(&p);
+                self.plan.requested_by.remove(&p);
+            }
+            Undo::Removed(p) => { self.removed.remove(&p); }
+            Undo::SatisfiedVia(p) => {
+                let via = self.satisfied_via.get_mut(&p).unwrap();
+                via.pop();
+                if via.is_empty() {
+                    self.satisfied_via.remove(&p);
+                }
+            }
+            Undo::TookSatisfiedVia(p, via) => { self.satisfied_via.insert(p, via); }
+        }
+    }
 }
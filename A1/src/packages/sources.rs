@@ -0,0 +1,323 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::debversion::DebianVersionNum;
+use crate::packages::{AlternativePolicy, Dependency, NoSuchPackage, PlannedRemoval, RelVersionedPackageNum, Render, SizeSummary};
+use crate::packages::deps_available::version_satisfies;
+use crate::packages::solvers::Planner;
+
+/// A source package from a Sources index.
+pub struct SourcePackage {
+    pub version : DebianVersionNum,
+    /// the binary packages it builds (its Binary field)
+    pub binaries : Vec<String>,
+    /// its build relationships, in the order of the fields and of the clauses within them
+    pub relations : Vec<BuildRelation>,
+}
+
+/// The build relationship fields of a Sources stanza.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BuildField { BuildDepends, BuildDependsIndep, BuildDependsArch, BuildConflicts }
+
+impl BuildField {
+    pub const ALL : [BuildField; 4] = [BuildField::BuildDepends, BuildField::BuildDependsIndep, BuildField::BuildDependsArch, BuildField::BuildConflicts];
+}
+
+impl fmt::Display for BuildField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", match self {
+            BuildField::BuildDepends => "Build-Depends",
+            BuildField::BuildDependsIndep => "Build-Depends-Indep",
+            BuildField::BuildDependsArch => "Build-Depends-Arch",
+            BuildField::BuildConflicts => "Build-Conflicts"
+        });
+    }
+}
+
+/// One comma-separated clause of a build relationship field: alternatives for the Build-Depends fields; for
+/// Build-Conflicts, which has no alternatives, a single package.
+pub struct BuildRelation {
+    pub field : BuildField,
+    pub alternatives : Vec<BuildAlternative>,
+}
+
+/// An alternative of a build relationship, with its architecture restriction, e.g. "foo [amd64 arm64]" or
+/// "foo [!hurd-any]"; it applies on every architecture if there is none.
+pub struct BuildAlternative {
+    pub dependency : RelVersionedPackageNum,
+    pub architectures : Vec<String>,
+}
+
+impl BuildAlternative {
+    /// Whether the alternative applies when building on arch: its architecture list names arch (or a wildcard that
+    /// matches it), or, if the list is negated, doesn't.
+    pub fn applies_on(&self, arch: &str) -> bool {
+        if self.architectures.is_empty() {
+            return true;
+        }
+        if self.architectures.iter().all(|a| a.starts_with('!')) {
+            return !self.architectures.iter().any(|a| arch_matches(&a[1..], arch));
+        }
+        return self.architectures.iter().any(|a| arch_matches(a, arch));
+    }
+}
+
+// the CPUs that dpkg knows (its cputable)
+const CPUS : [&str; 36] = [
+    "i386", "ia64", "alpha", "amd64", "arc", "armeb", "arm", "arm64", "avr32", "hppa", "loong64", "m32r", "m68k", "mips",
+    "mipsel", "mipsr6", "mipsr6el", "mips64", "mips64el", "mips64r6", "mips64r6el", "nios2", "or1k", "powerpc", "powerpcel",
+    "ppc64", "ppc64el", "riscv64", "s390", "s390x", "sh3", "sh3eb", "sh4", "sh4eb", "sparc", "sparc64",
+];
+
+// dpkg's tupletable: the abi-libc-os-cpu tuple of each Debian architecture name, where <cpu> stands for any of CPUS;
+// the first entry that matches a name counts
+const TUPLES : [(&str, &str); 29] = [
+    ("eabihf-musl-linux-arm", "musl-linux-armhf"),
+    ("base-musl-linux-<cpu>", "musl-linux-<cpu>"),
+    ("eabihf-gnu-linux-arm", "armhf"),
+    ("eabi-gnu-linux-arm", "armel"),
+    ("abin32-gnu-linux-mips64r6el", "mipsn32r6el"),
+    ("abin32-gnu-linux-mips64r6", "mipsn32r6"),
+    ("abin32-gnu-linux-mips64el", "mipsn32el"),
+    ("abin32-gnu-linux-mips64", "mipsn32"),
+    ("abi64-gnu-linux-mips64r6el", "mips64r6el"),
+    ("abi64-gnu-linux-mips64r6", "mips64r6"),
+    ("abi64-gnu-linux-mips64el", "mips64el"),
+    ("abi64-gnu-linux-mips64", "mips64"),
+    ("spe-gnu-linux-powerpc", "powerpcspe"),
+    ("x32-gnu-linux-amd64", "x32"),
+    ("base-gnu-linux-<cpu>", "<cpu>"),
+    ("eabihf-gnu-kfreebsd-arm", "kfreebsd-armhf"),
+    ("base-gnu-kfreebsd-<cpu>", "kfreebsd-<cpu>"),
+    ("base-gnu-knetbsd-<cpu>", "knetbsd-<cpu>"),
+    ("base-gnu-kopensolaris-<cpu>", "kopensolaris-<cpu>"),
+    ("base-gnu-hurd-<cpu>", "hurd-<cpu>"),
+    ("base-bsd-dragonflybsd-<cpu>", "dragonflybsd-<cpu>"),
+    ("base-bsd-freebsd-<cpu>", "freebsd-<cpu>"),
+    ("base-bsd-openbsd-<cpu>", "openbsd-<cpu>"),
+    ("base-bsd-netbsd-<cpu>", "netbsd-<cpu>"),
+    ("base-bsd-darwin-<cpu>", "darwin-<cpu>"),
+    ("base-sysv-aix-<cpu>", "aix-<cpu>"),
+    ("base-sysv-solaris-<cpu>", "solaris-<cpu>"),
+    ("eabi-uclibc-linux-arm", "uclibc-linux-armel"),
+    ("base-uclibc-linux-<cpu>", "uclibc-linux-<cpu>"),
+];
+
+// the abi, libc, os and cpu of a Debian architecture name (e.g. armhf is eabihf, gnu, linux, arm); None if dpkg doesn't
+// know it
+fn arch_tuple(arch: &str) -> Option<Vec<String>> {
+    for (tuple, name) in TUPLES.iter() {
+        let cpu = match name.strip_suffix("<cpu>") {
+            None if *name == arch => return Some(tuple.split('-').map(String::from).collect()),
+            None => continue,
+            Some(prefix) => match arch.strip_prefix(prefix) {
+                Some(cpu) if CPUS.contains(&cpu) => cpu,
+                _ => continue
+            }
+        };
+        return Some(tuple.replace("<cpu>", cpu).split('-').map(String::from).collect());
+    }
+    return None;
+}
+
+// the tuple that an architecture name or wildcard stands for, with "any" for the parts a wildcard leaves open: like
+// dpkg, a wildcard names the last parts of the tuple (linux-any is os and cpu, any-arm is cpu)
+fn wildcard_tuple(pattern: &str) -> Option<Vec<String>> {
+    let parts : Vec<&str> = pattern.splitn(4, '-').collect();
+    if !parts.contains(&"any") {
+        return arch_tuple(pattern);
+    }
+    let mut tuple = vec![String::from("any"); 4 - parts.len()];
+    tuple.extend(parts.iter().map(|part| part.to_string()));
+    return Some(tuple);
+}
+
+// whether an architecture name or wildcard (e.g. amd64, any, linux-any, any-arm, gnu-any-any) matches arch, comparing
+// their tuples as dpkg does: any-arm matches armel and armhf, linux-any every Linux architecture
+fn arch_matches(pattern: &str, arch: &str) -> bool {
+    if pattern == "any" || pattern == arch {
+        return true;
+    }
+    return match (wildcard_tuple(pattern), arch_tuple(arch)) {
+        (Some(wildcard), Some(tuple)) => wildcard.iter().zip(&tuple).all(|(w, t)| w == "any" || w == t),
+        _ => false
+    };
+}
+
+impl Packages {
+    /// Returns the source package source_name from the loaded Sources index, if any.
+    pub fn get_source(&self, source_name: &str) -> Option<&SourcePackage> {
+        return self.sources.get(source_name);
+    }
+
+    /// Works out what has to be installed to build source_name on arch: its Build-Depends, plus Build-Depends-Arch
+    /// if arch_parts and Build-Depends-Indep if indep_parts, with the alternatives that apply on arch. They are resolved
+    /// like how-to-install's requests, against the available and installed binary packages: those without alternatives
+    /// first, then the rest, each with its best alternative (by policy) whose available version satisfies it, and then
+    /// the dependencies of what they pull in. Installed packages that match a Build-Conflicts entry have to be removed
+    /// (what depends on them isn't looked at); packages that the plan needs and that match one can't be built with.
+    pub fn build_deps(&self, source_name: &str, arch: &str, arch_parts: bool, indep_parts: bool, policy: &dyn AlternativePolicy)
+            -> Result<BuildDeps, NoSuchPackage> {
+        let source = match self.sources.get(source_name) {
+            Some(source) => source,
+            None => return Err(self.no_such_source(source_name))
+        };
+        let fields : Vec<BuildField> = BuildField::ALL.iter().copied().filter(|f| match f {
+            BuildField::BuildDependsArch => arch_parts,
+            BuildField::BuildDependsIndep => indep_parts,
+            _ => true
+        }).collect();
+        let applying = |r: &BuildRelation| -> Dependency {
+            return r.alternatives.iter().filter(|a| a.applies_on(arch)).map(|a| a.dependency.clone()).collect();
+        };
+        let clauses : Vec<(BuildField, Dependency)> = source.relations.iter()
+            .filter(|r| r.field != BuildField::BuildConflicts && fields.contains(&r.field))
+            .map(|r| (r.field, applying(r)))
+            .filter(|(_, dep)| !dep.is_empty())
+            .collect();
+        let build_conflicts : Vec<RelVersionedPackageNum> = source.relations.iter()
+            .filter(|r| r.field == BuildField::BuildConflicts)
+            .flat_map(applying)
+            .collect();
+
+        // like the planner's worklist: clauses without alternatives first, so that choices can take them into account
+        let mut planner = Planner::with_policy(self, policy);
+        let mut unsatisfiable = vec![];
+        for with_alternatives in [false, true] {
+            for (field, dep) in clauses.iter().filter(|(_, dep)| (dep.len() > 1) == with_alternatives) {
+                if let Err(conflicts) = planner.require(dep) {
+                    let why = if conflicts.is_empty() {
+                        String::from("no alternative is available at a version that satisfies it")
+                    } else {
+                        conflicts.iter().map(|c| self.conflict2str(*c)).collect::<Vec<_>>().join("; ")
+                    };
+                    unsatisfiable.push(format!("{} {:?}: {}", field, self.dep2str(dep), why));
+                }
+            }
+            planner.run();
+        }
+
+        let plan = planner.plan();
+        let dependencies = clauses.iter().map(|(field, dep)| {
+            let satisfied_by = self.dep_is_satisfied_in(dep, |p| planner.planned(p));
+            BuildDependency {
+                field : field.to_string(),
+                dependency : self.dep2str(dep),
+                satisfied_by : satisfied_by.map(|p| self.get_package_name(p).to_string()),
+                installed : satisfied_by.is_some_and(|p| !plan.install.contains(&p)),
+            }
+        }).collect();
+        let (install, unavailable) : (Vec<i32>, Vec<i32>) = plan.install.iter().partition(|p| self.available_debvers.contains_key(p));
+        unsatisfiable.extend(plan.unsatisfiable.iter().map(|u| self.explain_unsatisfiable(u)));
+        unsatisfiable.extend(unavailable.iter().map(|p| format!("{} is not available (needed for {})",
+            self.get_package_name(*p), self.get_package_name(planner.requested_for(*p)))));
+        let mut removed : Vec<i32> = plan.remove.iter().map(|r| r.package_num).collect();
+        let mut to_remove : Vec<PlannedRemoval> = plan.remove.iter().map(|r| PlannedRemoval {
+            package : self.get_package_name(r.package_num).to_string(),
+            installed_version : self.installed_debvers[&r.package_num].to_string(),
+            reason : self.explain_removal(r),
+        }).collect();
+        for c in &build_conflicts {
+            let version = match planner.planned(c.package_num) {
+                Some(v) if version_satisfies(v, &c.rel_version) => v,
+                _ => continue
+            };
+            let conflict = format!("{} build-conflicts with {}", source_name, self.dep2str(&vec![c.clone()]));
+            if plan.install.contains(&c.package_num) {
+                unsatisfiable.push(format!("{}, but the build needs {} {}", conflict, self.get_package_name(c.package_num), version));
+            } else if !removed.contains(&c.package_num) {
+                removed.push(c.package_num);
+                to_remove.push(PlannedRemoval {
+                    package : self.get_package_name(c.package_num).to_string(),
+                    installed_version : version.to_string(),
+                    reason : conflict,
+                });
+            }
+        }
+        return Ok(BuildDeps {
+            source : source_name.to_string(),
+            version : source.version.to_string(),
+            architecture : arch.to_string(),
+            fields : fields.iter().map(|f| f.to_string()).collect(),
+            dependencies,
+            to_install : install.iter().map(|p| self.get_package_name(*p).to_string()).collect(),
+            to_remove,
+            unsatisfiable,
+            sizes : self.size_summary(&install, &removed, false),
+        });
+    }
+
+    // the error for an unknown source package: suggests the source packages that build a binary package of that name,
+    // then the closest source package names
+    fn no_such_source(&self, source_name: &str) -> NoSuchPackage {
+        let mut suggestions : Vec<String> = self.sources.iter()
+            .filter(|(_, s)| s.binaries.iter().any(|b| b == source_name))
+            .map(|(name, _)| name.clone())
+            .collect();
+        suggestions.sort();
+        for name in self.suggest_source_names(source_name) {
+            if !suggestions.iter().any(|s| s == name) {
+                suggestions.push(name.to_string());
+            }
+        }
+        return NoSuchPackage { package : source_name.to_string(), suggestions };
+    }
+}
+
+/// Result of build-deps: what has to be installed (and removed) to build a source package.
+#[derive(Serialize)]
+pub struct BuildDeps {
+    pub source : String,
+    pub version : String,
+    /// the architecture the build is for
+    pub architecture : String,
+    /// the build relationship fields that count
+    pub fields : Vec<String>,
+    pub dependencies : Vec<BuildDependency>,
+    pub to_install : Vec<String>,
+    pub to_remove : Vec<PlannedRemoval>,
+    /// build dependencies and dependencies that can't be satisfied, and build conflicts that can't be avoided
+    pub unsatisfiable : Vec<String>,
+    pub sizes : SizeSummary,
+}
+
+/// A build dependency clause (with the alternatives that apply on the architecture) and what satisfies it.
+#[derive(Serialize)]
+pub struct BuildDependency {
+    pub field : String,
+    pub dependency : String,
+    /// None if nothing does
+    pub satisfied_by : Option<String>,
+    /// whether satisfied_by is already installed (and stays as it is)
+    pub installed : bool,
+}
+
+impl Render for BuildDeps {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Source {} {} ({}, {}):", self.source, self.version, self.architecture, self.fields.join(", "))?;
+        for d in &self.dependencies {
+            match &d.satisfied_by {
+                Some(p) => writeln!(out, "- {} {:?}: {}{}", d.field, d.dependency, p, if d.installed { " (installed)" } else { "" })?,
+                None => writeln!(out, "- {} {:?}: not satisfied", d.field, d.dependency)?
+            }
+        }
+        writeln!(out, "{:?} to install: {:?}", self.source, self.to_install.join(", "))?;
+        if !self.to_remove.is_empty() {
+            let names = self.to_remove.iter().map(|r| &r.package[..]).collect::<Vec<_>>();
+            writeln!(out, "{:?} to remove: {:?}", self.source, names.join(", "))?;
+            for r in &self.to_remove {
+                writeln!(out, "- {} {} has to be removed: {}", r.package, r.installed_version, r.reason)?;
+            }
+        }
+        if !self.unsatisfiable.is_empty() {
+            writeln!(out, "{:?} can't be built:", self.source)?;
+            for u in &self.unsatisfiable {
+                writeln!(out, "- {}", u)?;
+            }
+        }
+        return self.sizes.render(out);
+    }
+}
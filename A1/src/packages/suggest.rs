@@ -0,0 +1,120 @@
+use std::cmp;
+use std::collections::HashMap;
+
+use crate::Packages;
+use crate::packages::NoSuchPackage;
+
+// how many names we run the (comparatively expensive) edit distance on per lookup, give or take ties
+const MAX_CANDIDATES : usize = 2000;
+const MAX_SUGGESTIONS : usize = 5;
+
+/// Trigram index over package names, used to find close matches for misspelled names.
+/// Names are padded with "$$" in front and "$" at the end, so the first grams also act as a prefix index.
+pub struct NameIndex {
+    grams : HashMap<String, Vec<i32>>,
+}
+
+impl NameIndex {
+    pub fn new() -> NameIndex {
+        NameIndex { grams : HashMap::new() }
+    }
+
+    pub fn insert(&mut self, package_name: &str, package_num: i32) {
+        for gram in trigrams(package_name) {
+            let postings = self.grams.entry(gram).or_default();
+            // the same gram can occur twice in a name; only record the package once
+            if postings.last() != Some(&package_num) {
+                postings.push(package_num);
+            }
+        }
+    }
+
+    /// Returns the `limit` package numbers sharing the most trigrams with `name`, best first, and any others sharing as
+    /// many as the last of them: a typo can leave a name with few grams in common with the right one, and then the tie
+    /// at the cutoff is what it falls into.
+    pub fn candidates(&self, name: &str, limit: usize) -> Vec<(i32, usize)> {
+        let mut shared : HashMap<i32, usize> = HashMap::new();
+        let mut query_grams = trigrams(name);
+        query_grams.sort();
+        query_grams.dedup();
+        for gram in query_grams {
+            if let Some(postings) = self.grams.get(&gram) {
+                for pnum in postings {
+                    *shared.entry(*pnum).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut result : Vec<(i32, usize)> = shared.into_iter().collect();
+        result.sort_by(|(p1, s1), (p2, s2)| s2.cmp(s1).then(p1.cmp(p2)));
+        if limit == 0 {
+            return vec![];
+        }
+        if let Some(&(_, cutoff)) = result.get(limit - 1) {
+            let end = result.iter().position(|(_, s)| *s < cutoff).unwrap_or(result.len());
+            result.truncate(end);
+        }
+        return result;
+    }
+}
+
+fn trigrams(name: &str) -> Vec<String> {
+    let padded : Vec<char> = format!("$${}$", name).chars().collect();
+    return padded.windows(3).map(|w| w.iter().collect()).collect();
+}
+
+/// Optimal string alignment distance: Levenshtein plus transposition of adjacent characters,
+/// which is the most common typo in package names (e.g. "bsah").
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) : (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() { row[0] = i; }
+    for (j, cell) in d[0].iter_mut().enumerate() { *cell = j; }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i-1] == b[j-1] { 0 } else { 1 };
+            d[i][j] = cmp::min(cmp::min(d[i-1][j] + 1, d[i][j-1] + 1), d[i-1][j-1] + cost);
+            if i > 1 && j > 1 && a[i-1] == b[j-2] && a[i-2] == b[j-1] {
+                d[i][j] = cmp::min(d[i][j], d[i-2][j-2] + 1);
+            }
+        }
+    }
+    return d[a.len()][b.len()];
+}
+
+// the names in index closest to name by edit distance, closest first; name_of maps the index's numbers to names
+fn closest_names<'a>(index: &NameIndex, name: &str, name_of: impl Fn(i32) -> &'a str) -> Vec<&'a str> {
+    let length = name.chars().count();
+    let max_distance = cmp::max(2, length / 3);
+    let mut scored : Vec<(usize, usize, &str)> = index
+        .candidates(name, MAX_CANDIDATES)
+        .into_iter()
+        .map(|(num, shared)| (name_of(num), shared))
+        // the lengths alone take this many edits to even out
+        .filter(|(candidate, _)| (candidate.chars().count() as isize - length as isize).unsigned_abs() <= max_distance)
+        .map(|(candidate, shared)| (edit_distance(name, candidate), shared, candidate))
+        .filter(|(dist, _, _)| *dist <= max_distance)
+        .collect();
+    scored.sort_by(|(d1, s1, n1), (d2, s2, n2)| d1.cmp(d2).then(s2.cmp(s1)).then(n1.cmp(n2)));
+    return scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, _, name)| name).collect();
+}
+
+impl Packages {
+    /// Returns the known package names closest to package_name by edit distance, closest first.
+    /// Only names within a third of the query's length (but at least 2 edits) are considered close.
+    pub fn suggest_package_names(&self, package_name: &str) -> Vec<&str> {
+        return closest_names(&self.name_index, package_name, |pnum| self.get_package_name(pnum));
+    }
+
+    /// Like suggest_package_names, but over the source package names of the Sources index.
+    pub fn suggest_source_names(&self, source_name: &str) -> Vec<&str> {
+        return closest_names(&self.source_name_index, source_name, |num| &self.source_names[num as usize]);
+    }
+
+    /// Builds the error returned by queries on an unknown package_name, including the nearest known names.
+    pub fn no_such_package(&self, package_name: &str) -> NoSuchPackage {
+        return NoSuchPackage {
+            package : package_name.to_string(),
+            suggestions : self.suggest_package_names(package_name).into_iter().map(String::from).collect(),
+        };
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{ClosureEngine, Render};
+
+impl Packages {
+    /// The reverse dependency index of the available packages (or the installed ones if installed), indexed by package
+    /// number: for each package, the packages in the set with a Depends or Pre-Depends clause that it is an alternative
+    /// of, sorted. Installed packages have the dependencies of the Packages index.
+    pub fn reverse_dependencies(&self, installed: bool) -> Vec<Vec<usize>> {
+        let in_set = |p: i32| if installed { self.installed_debvers.contains_key(&p) } else { self.available_debvers.contains_key(&p) };
+        let mut reverse : Vec<Vec<usize>> = vec![vec![]; self.package_num_to_name.len()];
+        for p in (0..self.package_num_to_name.len() as i32).filter(|p| in_set(*p)) {
+            let clauses = self.dependencies[&p].iter().chain(self.pre_dependencies.get(&p).map_or(&[][..], |d| &d[..]));
+            for a in clauses.flatten().filter(|a| in_set(a.package_num)) {
+                reverse[a.package_num as usize].push(p as usize);
+            }
+        }
+        for r in reverse.iter_mut() {
+            r.dedup();
+        }
+        return reverse;
+    }
+
+    /// Ranks the available packages (or the installed ones if installed) by how many packages of the set depend on
+    /// them, directly or not: the size of their closure in the reverse dependency graph (which counts the package itself
+    /// only if it is in a dependency cycle), then by direct reverse dependencies, then by name. Only the first n.
+    pub fn top_depended(&self, installed: bool, n: usize) -> TopDepended {
+        let in_set = |p: i32| if installed { self.installed_debvers.contains_key(&p) } else { self.available_debvers.contains_key(&p) };
+        let reverse = self.reverse_dependencies(installed);
+        let sizes = ClosureEngine::new(&reverse).closure_sizes();
+        let mut ranked : Vec<ReverseClosure> = (0..self.package_num_to_name.len() as i32).filter(|p| in_set(*p))
+            .map(|p| ReverseClosure {
+                package : self.get_package_name(p).to_string(),
+                reverse_dependencies : sizes[p as usize],
+                direct : reverse[p as usize].len(),
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.reverse_dependencies.cmp(&a.reverse_dependencies)
+            .then_with(|| b.direct.cmp(&a.direct))
+            .then_with(|| a.package.cmp(&b.package)));
+        let packages = ranked.len();
+        ranked.truncate(n);
+        return TopDepended {
+            scope : if installed { "installed packages" } else { "available packages" }.to_string(),
+            packages,
+            ranked,
+        };
+    }
+}
+
+/// Result of top-depended: packages ranked by how many packages depend on them, directly or not.
+#[derive(Serialize)]
+pub struct TopDepended {
+    /// "available packages" or "installed packages"
+    pub scope : String,
+    /// how many packages are in it
+    pub packages : usize,
+    /// most depended upon first; only the first few
+    pub ranked : Vec<ReverseClosure>,
+}
+
+#[derive(Serialize)]
+pub struct ReverseClosure {
+    pub package : String,
+    /// the size of its transitive reverse dependency closure
+    pub reverse_dependencies : usize,
+    /// how many packages depend on it directly
+    pub direct : usize,
+}
+
+impl Render for TopDepended {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "most depended upon of {} {} (all reverse dependencies, direct ones):", self.packages, self.scope)?;
+        for r in &self.ranked {
+            writeln!(out, "{:>7} {:>7} {}", r.reverse_dependencies, r.direct, r.package)?;
+        }
+        return Ok(());
+    }
+}
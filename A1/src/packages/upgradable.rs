@@ -0,0 +1,134 @@
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::Render;
+
+/// Order of the list-upgradable output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UpgradableSort {
+    Name,
+    /// by section, then name; packages without a section come last
+    Section,
+    /// upgrades, then downgrades, then installed-only packages, each by name
+    Kind,
+}
+
+impl FromStr for UpgradableSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(UpgradableSort::Name),
+            "section" => Ok(UpgradableSort::Section),
+            "kind" => Ok(UpgradableSort::Kind),
+            _ => Err(format!("unknown sort order {} (expected name, section or kind)", s))
+        }
+    }
+}
+
+pub struct UpgradableOptions {
+    pub sort : UpgradableSort,
+    /// only report packages in one of these sections (see list_upgradable); empty means all packages
+    pub sections : Vec<String>,
+}
+
+impl Default for UpgradableOptions {
+    fn default() -> Self {
+        UpgradableOptions { sort : UpgradableSort::Name, sections : vec![] }
+    }
+}
+
+impl Packages {
+    /// Compares every installed package's version against the available one (using the Debian ordering) and reports
+    /// upgrades, downgrades, and packages that are installed but not in the Packages index. Up-to-date packages are left out.
+    /// A package's section is the one in the Packages index or, for installed-only packages, in the installed packages file.
+    pub fn list_upgradable(&self, options: &UpgradableOptions) -> UpgradableReport {
+        let mut packages = vec![];
+        for (package_num, installed) in &self.installed_debvers {
+            let package_name = self.get_package_name(*package_num);
+            let section = self.sections.get(package_num).or_else(|| self.installed_sections.get(package_num));
+            if !options.sections.is_empty() && !section.is_some_and(|s| options.sections.contains(s)) {
+                continue;
+            }
+            let available = self.available_debvers.get(package_num);
+            let kind = match available.map(|a| a.cmp(installed)) {
+                None => UpgradeKind::InstalledOnly,
+                Some(Ordering::Greater) => UpgradeKind::Upgrade,
+                Some(Ordering::Less) => UpgradeKind::Downgrade,
+                Some(Ordering::Equal) => continue
+            };
+            packages.push(UpgradableEntry {
+                package : package_name.to_string(),
+                section : section.cloned(),
+                installed_version : installed.to_string(),
+                available_version : available.map(|a| a.to_string()),
+                kind,
+            });
+        }
+
+        match options.sort {
+            UpgradableSort::Name => packages.sort_by(|a, b| a.package.cmp(&b.package)),
+            UpgradableSort::Section => packages.sort_by(|a, b| {
+                // None sorts before Some, but sectionless packages belong at the end
+                (a.section.is_none(), &a.section, &a.package).cmp(&(b.section.is_none(), &b.section, &b.package))
+            }),
+            UpgradableSort::Kind => packages.sort_by(|a, b| (a.kind, &a.package).cmp(&(b.kind, &b.package)))
+        }
+
+        let count = |kind| packages.iter().filter(|p| p.kind == kind).count();
+        return UpgradableReport {
+            upgrades : count(UpgradeKind::Upgrade),
+            downgrades : count(UpgradeKind::Downgrade),
+            installed_only : count(UpgradeKind::InstalledOnly),
+            packages,
+        };
+    }
+}
+
+/// Result of the list-upgradable query, with counts over the listed packages.
+#[derive(Serialize)]
+pub struct UpgradableReport {
+    pub packages : Vec<UpgradableEntry>,
+    pub upgrades : usize,
+    pub downgrades : usize,
+    pub installed_only : usize,
+}
+
+#[derive(Serialize)]
+pub struct UpgradableEntry {
+    pub package : String,
+    pub section : Option<String>,
+    pub installed_version : String,
+    /// None for installed-only packages
+    pub available_version : Option<String>,
+    pub kind : UpgradeKind,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeKind {
+    Upgrade,
+    Downgrade,
+    InstalledOnly,
+}
+
+impl Render for UpgradableReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        for p in &self.packages {
+            match &p.section {
+                Some(section) => write!(out, "{} ({}): ", p.package, section)?,
+                None => write!(out, "{}: ", p.package)?
+            }
+            match (&p.available_version, p.kind) {
+                (Some(available), UpgradeKind::Upgrade) => writeln!(out, "{} -> {} [upgrade]", p.installed_version, available)?,
+                (Some(available), _) => writeln!(out, "{} -> {} [downgrade]", p.installed_version, available)?,
+                (None, _) => writeln!(out, "{} [installed only]", p.installed_version)?
+            }
+        }
+        return writeln!(out, "{} upgradable, {} downgrades, {} installed only", self.upgrades, self.downgrades, self.installed_only);
+    }
+}
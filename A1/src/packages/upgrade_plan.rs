@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::debversion::DebianVersionNum;
+use crate::packages::{ConflictIndex, ConflictRef, PlannedRemoval, PlannedVersion, Removal, Render, SizeSummary, VersionChange};
+use crate::packages::solvers::Planner;
+
+// something wrong with a (hypothetical) installed state
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Problem {
+    // a Depends or Pre-Depends clause (package, index, as numbered by install_clause) that isn't satisfied
+    Unsatisfied(i32, usize),
+    // two packages that can't be installed together
+    Conflict(i32, i32, ConflictRef),
+}
+
+// the installed state that one planning round ends up with
+struct Round<'a> {
+    versions : HashMap<i32, &'a DebianVersionNum>,
+    // packages to upgrade or install
+    changed : Vec<i32>,
+    remove : Vec<Removal>,
+    // changed or removed package -> the upgrade that brought the change about
+    cause : HashMap<i32, i32>,
+}
+
+impl Packages {
+    /// Plans bringing the installed packages up to their available versions. Without dist, like apt's upgrade: installed
+    /// packages are upgraded, nothing is installed or removed. With dist, like apt's dist-upgrade: the upgrades may also
+    /// install new packages and remove conflicting ones, chosen the way how-to-install does (keeping the installed packages'
+    /// dependencies satisfied). Either way, the resulting state is checked like deps_available checks a package, plus
+    /// Conflicts/Breaks; an upgrade that leads to an unsatisfied dependency or a conflict is kept back, and we plan again.
+    /// Problems that the installed state already has aren't blamed on any upgrade.
+    pub fn plan_upgrade(&self, dist: bool) -> UpgradePlan {
+        let index = self.conflict_index();
+        let mut installed : Vec<i32> = self.installed_debvers.keys().copied().collect();
+        installed.sort_by_key(|p| self.get_package_name(*p));
+        let upgradable : Vec<i32> = installed.iter().copied()
+            .filter(|p| self.available_debvers.get(p).is_some_and(|a| a > &self.installed_debvers[p]))
+            .collect();
+        let baseline : HashSet<Problem> = self.problems(&index, &installed, |p| self.installed_debvers.get(&p)).into_iter().collect();
+
+        let mut kept_back : Vec<(i32, String)> = vec![];
+        let round = loop {
+            let seeds : Vec<i32> = upgradable.iter().copied().filter(|p| !kept_back.iter().any(|(k, _)| k == p)).collect();
+            let round = self.upgrade_round(dist, &seeds, &installed);
+            let mut package_nums : Vec<i32> = round.versions.keys().copied().collect();
+            package_nums.sort();
+            let mut progress = false;
+            for problem in self.problems(&index, &package_nums, |p| round.versions.get(&p).copied()) {
+                if baseline.contains(&problem) { continue; }
+                let culprit = match problem {
+                    Problem::Unsatisfied(q, i) => round.cause.get(&q).or_else(|| {
+                        self.install_clause(q, i).0.iter().find_map(|alternative| round.cause.get(&alternative.package_num))
+                    }),
+                    Problem::Conflict(a, b, _) => round.cause.get(&a).or_else(|| round.cause.get(&b))
+                };
+                if let Some(seed) = culprit {
+                    if !kept_back.iter().any(|(k, _)| k == seed) {
+                        kept_back.push((*seed, self.explain_problem(problem, &round.versions)));
+                        progress = true;
+                    }
+                }
+            }
+            if !progress {
+                break round;
+            }
+        };
+
+        let removed : Vec<i32> = round.remove.iter().map(|r| r.package_num).collect();
+        let mut plan = UpgradePlan {
+            dist_upgrade : dist,
+            upgrade : vec![],
+            install : vec![],
+            remove : vec![],
+            kept_back : vec![],
+            sizes : self.size_summary(&round.changed, &removed, false),
+        };
+        for p in round.changed {
+            let name = self.get_package_name(p).to_string();
+            let to = self.available_debvers[&p].to_string();
+            match self.installed_debvers.get(&p) {
+                Some(from) => plan.upgrade.push(VersionChange { package : name, from : from.to_string(), to }),
+                None => plan.install.push(PlannedVersion { package : name, version : to })
+            }
+        }
+        for r in round.remove {
+            plan.remove.push(PlannedRemoval {
+                package : self.get_package_name(r.package_num).to_string(),
+                installed_version : self.installed_debvers[&r.package_num].to_string(),
+                reason : self.conflict2str(r.conflict),
+            });
+        }
+        for (p, reason) in kept_back {
+            plan.kept_back.push(KeptBack {
+                package : self.get_package_name(p).to_string(),
+                from : self.installed_debvers[&p].to_string(),
+                to : self.available_debvers[&p].to_string(),
+                reason,
+            });
+        }
+        plan.upgrade.sort_by(|a, b| a.package.cmp(&b.package));
+        plan.install.sort_by(|a, b| a.package.cmp(&b.package));
+        plan.kept_back.sort_by(|a, b| a.package.cmp(&b.package));
+        return plan;
+    }
+
+    fn upgrade_round(&self, dist: bool, seeds: &[i32], installed: &[i32]) -> Round<'_> {
+        let mut versions : HashMap<i32, &DebianVersionNum> = self.installed_debvers.iter().map(|(p, v)| (*p, v)).collect();
+        if !dist {
+            for s in seeds {
+                versions.insert(*s, &self.available_debvers[s]);
+            }
+            return Round { versions, changed : seeds.to_vec(), remove : vec![], cause : seeds.iter().map(|s| (*s, *s)).collect() };
+        }
+
+        let mut planner = Planner::new(self);
+        for p in installed {
+            if self.available_debvers.get(p) == Some(&self.installed_debvers[p]) {
+                planner.keep_satisfied(*p);
+            }
+        }
+        for s in seeds {
+            planner.select(*s, None);
+        }
+        planner.run();
+        let plan = planner.plan();
+        let mut cause = HashMap::new();
+        for p in &plan.install {
+            cause.insert(*p, planner.requested_for(*p));
+            match planner.planned(*p) {
+                Some(v) => versions.insert(*p, v),
+                None => versions.remove(p)
+            };
+        }
+        for r in &plan.remove {
+            cause.insert(r.package_num, planner.requested_for(r.installing));
+            versions.remove(&r.package_num);
+        }
+        // packages that the greedy planner picks without an available version can't be installed; the dependency
+        // that asked for them will show up as unsatisfied
+        let changed = plan.install.iter().copied().filter(|p| self.available_debvers.contains_key(p)).collect();
+        return Round { versions, changed, remove : plan.remove.clone(), cause };
+    }
+
+    fn problems<'a>(&self, index: &ConflictIndex, package_nums: &[i32], version_of: impl Fn(i32) -> Option<&'a DebianVersionNum>) -> Vec<Problem> {
+        let mut result : Vec<Problem> = self.unsatisfied_deps_in(package_nums, &version_of)
+            .into_iter()
+            .map(|(p, i)| Problem::Unsatisfied(p, i))
+            .collect();
+        for p in package_nums {
+            if let Some(v) = version_of(*p) {
+                // each conflict is found from both ends
+                for (other, conflict) in self.conflicts_between(index, *p, v, &version_of) {
+                    if *p < other {
+                        result.push(Problem::Conflict(*p, other, conflict));
+                    }
+                }
+            }
+        }
+        return result;
+    }
+
+    fn explain_problem(&self, problem: Problem, versions: &HashMap<i32, &DebianVersionNum>) -> String {
+        return match problem {
+            Problem::Unsatisfied(q, i) => {
+                let (dep, relation) = self.install_clause(q, i);
+                format!("{} {} {} {:?}, which wouldn't be satisfied", self.get_package_name(q), versions[&q], relation, self.dep2str(dep))
+            }
+            Problem::Conflict(a, b, conflict) => format!("{} ({} {}, {} {})", self.conflict2str(conflict),
+                self.get_package_name(a), versions[&a], self.get_package_name(b), versions[&b])
+        }
+    }
+}
+
+/// Result of the plan-upgrade and plan-dist-upgrade queries. install and remove are always empty for plan-upgrade.
+#[derive(Serialize)]
+pub struct UpgradePlan {
+    pub dist_upgrade : bool,
+    pub upgrade : Vec<VersionChange>,
+    pub install : Vec<PlannedVersion>,
+    pub remove : Vec<PlannedRemoval>,
+    /// upgrades left out because they would leave a dependency unsatisfied or a conflict
+    pub kept_back : Vec<KeptBack>,
+    /// of upgrading, installing and removing
+    pub sizes : SizeSummary,
+}
+
+#[derive(Serialize)]
+pub struct KeptBack {
+    pub package : String,
+    pub from : String,
+    pub to : String,
+    pub reason : String,
+}
+
+impl Render for UpgradePlan {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        for c in &self.upgrade {
+            writeln!(out, "{}: {} -> {} [upgrade]", c.package, c.from, c.to)?;
+        }
+        for p in &self.install {
+            writeln!(out, "{}: none -> {} [new]", p.package, p.version)?;
+        }
+        for r in &self.remove {
+            writeln!(out, "{}: {} -> none [remove: {}]", r.package, r.installed_version, r.reason)?;
+        }
+        for k in &self.kept_back {
+            writeln!(out, "{}: {} -> {} [kept back: {}]", k.package, k.from, k.to, k.reason)?;
+        }
+        writeln!(out, "{} upgraded, {} newly installed, {} to remove, {} kept back",
+            self.upgrade.len(), self.install.len(), self.remove.len(), self.kept_back.len())?;
+        return self.sizes.render(out);
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::debversion::{DebianVersionNum, VersionRelation};
+use crate::packages::{AlternativePolicy, NoSuchPackage, PolicyContext, RelVersionedPackageNum, Render};
+use crate::packages::deps_available::version_satisfies;
+
+// a package in the closure: the package whose dependency pulled it in, and the dependencies (with their owner) that it
+// was chosen for or counted towards
+struct Selection<'a> {
+    package_num : i32,
+    pulled_in_by : i32,
+    placed : Vec<(i32, &'a RelVersionedPackageNum)>,
+}
+
+// a bound (version, strict) below or above which a constraint rules versions out
+type Bound<'a> = Option<(&'a DebianVersionNum, bool)>;
+
+fn bounds<'a>(rel: &VersionRelation, ver: &'a DebianVersionNum) -> (Bound<'a>, Bound<'a>) {
+    return match rel {
+        VersionRelation::StrictlyLess => (None, Some((ver, true))),
+        VersionRelation::LessOrEqual => (None, Some((ver, false))),
+        VersionRelation::Equal => (Some((ver, false)), Some((ver, false))),
+        VersionRelation::GreaterOrEqual => (Some((ver, false)), None),
+        VersionRelation::StrictlyGreater => (Some((ver, true)), None)
+    }
+}
+
+// whether no version is at or above lower and at or below upper
+fn empty_range(lower: Bound, upper: Bound) -> bool {
+    return match (lower, upper) {
+        (Some((lo, lo_strict)), Some((hi, hi_strict))) => lo > hi || (lo == hi && (lo_strict || hi_strict)),
+        _ => false
+    }
+}
+
+// whether some version satisfies both constraints; versions are totally ordered, so constraints that are pairwise
+// compatible can all be satisfied at once
+fn compatible(first: (&VersionRelation, &DebianVersionNum), second: (&VersionRelation, &DebianVersionNum)) -> bool {
+    let (lower1, upper1) = bounds(first.0, first.1);
+    let (lower2, upper2) = bounds(second.0, second.1);
+    return !empty_range(lower1, upper2) && !empty_range(lower2, upper1);
+}
+
+impl Packages {
+    /// Like transitive_dep_solution_with, but minding version constraints: a Depends or Pre-Depends clause is satisfied by a package picked
+    /// already if its available version will do, and otherwise by the alternative that policy ranks first among those
+    /// whose available version satisfies the clause (the first one overall if there is none). Every versioned constraint
+    /// that a clause places on the package satisfying it is recorded and checked against the available version; pairs of
+    /// constraints that no version could satisfy at once are flagged as conflicting.
+    pub fn versioned_dep_solution(&self, package_name: &str, policy: &dyn AlternativePolicy) -> Result<VersionedDepSolution, NoSuchPackage> {
+        let root = self.find_package_num(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        let available_ok = |a: &RelVersionedPackageNum| self.available_debvers.get(&a.package_num).is_some_and(|v| version_satisfies(v, &a.rel_version));
+
+        let mut selections : Vec<Selection> = vec![];
+        let mut index_of : HashMap<i32, usize> = HashMap::new();
+        let mut worklist = VecDeque::new();
+        worklist.push_back(root);
+        while let Some(p) = worklist.pop_front() {
+            for dep in self.install_clauses(p) {
+                if let Some(a) = dep.iter().find(|a| index_of.contains_key(&a.package_num) && available_ok(a)) {
+                    selections[index_of[&a.package_num]].placed.push((p, a));
+                    continue;
+                }
+                let ranked = {
+                    let selected = |q: i32| index_of.contains_key(&q);
+                    let context = PolicyContext { packages : self, installed : &|q| self.installed_debvers.get(&q), selected : &selected };
+                    policy.rank(&context, dep)
+                };
+                let usable = |q: &i32| dep.iter().any(|a| a.package_num == *q && available_ok(a));
+                let choice = match ranked.iter().copied().find(usable).or(ranked.first().copied()) {
+                    Some(choice) => choice,
+                    None => continue
+                };
+                let a = dep.iter().filter(|a| a.package_num == choice).find(|a| available_ok(a))
+                    .unwrap_or_else(|| dep.iter().find(|a| a.package_num == choice).unwrap());
+                let i = *index_of.entry(choice).or_insert_with(|| {
+                    selections.push(Selection { package_num : choice, pulled_in_by : p, placed : vec![] });
+                    worklist.push_back(choice);
+                    selections.len() - 1
+                });
+                selections[i].placed.push((p, a));
+            }
+        }
+
+        let solution : Vec<VersionedPackage> = selections.iter().map(|s| self.versioned_package(s)).collect();
+        return Ok(VersionedDepSolution {
+            package : package_name.to_string(),
+            version : self.available_debvers.get(&root).map(|v| v.to_string()),
+            consistent : solution.iter().all(|v| v.version.is_some() && v.unmet.is_empty() && v.conflicting.is_empty()),
+            solution,
+        });
+    }
+
+    // the chosen version of a selected package, checked against the constraints placed on it
+    fn versioned_package(&self, selection: &Selection) -> VersionedPackage {
+        let available = self.available_debvers.get(&selection.package_num);
+        let mut constraints = vec![];
+        let mut unmet = vec![];
+        let mut parsed : Vec<(&VersionRelation, DebianVersionNum)> = vec![];
+        for (owner, a) in &selection.placed {
+            let (rel, ver) = match &a.rel_version {
+                Some(rel_version) => rel_version,
+                None => continue
+            };
+            let placed = PlacedConstraint { by : self.get_package_name(*owner).to_string(), constraint : format!("{} {}", rel, ver) };
+            if available.is_some_and(|v| !version_satisfies(v, &a.rel_version)) {
+                unmet.push(placed.clone());
+            }
+            constraints.push(placed);
+            parsed.push((rel, ver.parse::<DebianVersionNum>().unwrap()));
+        }
+        let mut conflicting = vec![];
+        for i in 0..parsed.len() {
+            for j in i + 1..parsed.len() {
+                if !compatible((parsed[i].0, &parsed[i].1), (parsed[j].0, &parsed[j].1)) {
+                    conflicting.push((constraints[i].clone(), constraints[j].clone()));
+                }
+            }
+        }
+        return VersionedPackage {
+            package : self.get_package_name(selection.package_num).to_string(),
+            version : available.map(|v| v.to_string()),
+            pulled_in_by : self.get_package_name(selection.pulled_in_by).to_string(),
+            constraints,
+            unmet,
+            conflicting,
+        };
+    }
+}
+
+/// Result of transitive-dep-solution --versions: the packages of the closure with the versions chosen for them, and the
+/// version constraints placed on each.
+#[derive(Serialize)]
+pub struct VersionedDepSolution {
+    pub package : String,
+    /// package's available version
+    pub version : Option<String>,
+    pub solution : Vec<VersionedPackage>,
+    /// whether every package is available and its available version meets every constraint placed on it
+    pub consistent : bool,
+}
+
+/// A package of a versioned transitive dependency solution.
+#[derive(Serialize)]
+pub struct VersionedPackage {
+    pub package : String,
+    /// the available version; None if the package isn't available
+    pub version : Option<String>,
+    /// the package whose dependency brought this one in
+    pub pulled_in_by : String,
+    pub constraints : Vec<PlacedConstraint>,
+    /// the constraints that version doesn't meet
+    pub unmet : Vec<PlacedConstraint>,
+    /// pairs of constraints that no version could meet at once
+    pub conflicting : Vec<(PlacedConstraint, PlacedConstraint)>,
+}
+
+/// A version constraint, such as ">= 2.14", and the package whose dependency placed it.
+#[derive(Serialize, Clone)]
+pub struct PlacedConstraint {
+    pub by : String,
+    pub constraint : String,
+}
+
+impl fmt::Display for PlacedConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{} (from {})", self.constraint, self.by);
+    }
+}
+
+impl Render for VersionedDepSolution {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{:?} {} transitive dependency solution:", self.package, self.version.as_deref().unwrap_or("(not available)"))?;
+        for p in &self.solution {
+            write!(out, "  {} {}", p.package, p.version.as_deref().unwrap_or("(not available)"))?;
+            if !p.constraints.is_empty() {
+                write!(out, ", needs {}", p.constraints.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", "))?;
+            }
+            for c in &p.unmet {
+                write!(out, "; {} is not {}", p.version.as_deref().unwrap_or_default(), c.constraint)?;
+            }
+            for (c1, c2) in &p.conflicting {
+                write!(out, "; {} and {} conflict", c1, c2)?;
+            }
+            writeln!(out)?;
+        }
+        if !self.consistent {
+            writeln!(out, "no consistent set of versions: the closure can't be installed as chosen")?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+use std::io::{self, Write};
+
+use petgraph::Graph;
+use petgraph::algo::dominators::simple_fast;
+use petgraph::graph::NodeIndex;
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{AlternativePolicy, NoSuchPackage, PolicyContext, Render};
+use crate::packages::sizes::human_size;
+
+impl Packages {
+    /// Weighs package_name's dependencies: builds the graph of its transitive dependency solution (picked as
+    /// transitive_dep_solution_with does, with policy, but following Pre-Depends as well as Depends), with an edge from
+    /// each package to every alternative of its Depends and Pre-Depends that is in the solution, and computes its
+    /// dominator tree. A dependency dominates the packages that can
+    /// only be reached through it, itself included: those that dropping it would save. The dependencies come heaviest
+    /// first (by number of packages, then by Installed-Size, then by name); only the first top if top is Some.
+    pub fn weigh(&self, package_name: &str, policy: &dyn AlternativePolicy, top: Option<usize>) -> Result<Weight, NoSuchPackage> {
+        let root = self.find_package_num(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        let mut graph : Graph<i32, ()> = Graph::new();
+        let mut nodes : HashMap<i32, NodeIndex> = HashMap::new();
+        nodes.insert(root, graph.add_node(root));
+        let mut worklist = VecDeque::new();
+        worklist.push_back(root);
+        while let Some(p) = worklist.pop_front() {
+            for dep in self.install_clauses(p) {
+                let item = {
+                    let selected = |q: i32| nodes.contains_key(&q);
+                    let context = PolicyContext { packages : self, installed : &|q| self.installed_debvers.get(&q), selected : &selected };
+                    policy.rank(&context, dep).first().copied()
+                };
+                if let Some(item) = item {
+                    if let Entry::Vacant(entry) = nodes.entry(item) {
+                        entry.insert(graph.add_node(item));
+                        worklist.push_back(item);
+                    }
+                }
+            }
+        }
+        for (p, from) in &nodes {
+            for dep in self.install_clauses(*p) {
+                for a in dep {
+                    if let Some(to) = nodes.get(&a.package_num) {
+                        graph.update_edge(*from, *to, ());
+                    }
+                }
+            }
+        }
+
+        // each node's children in the dominator tree; every node is reachable from the root, so it has an immediate
+        // dominator, except the root
+        let dominators = simple_fast(&graph, nodes[&root]);
+        let mut children : Vec<Vec<NodeIndex>> = vec![vec![]; graph.node_count()];
+        for n in graph.node_indices() {
+            if let Some(d) = dominators.immediate_dominator(n) {
+                children[d.index()].push(n);
+            }
+        }
+        let disk_size = |p: i32| self.available_disk_sizes.get(&p).copied().unwrap_or(0);
+        let mut dependencies = vec![];
+        for n in graph.node_indices().filter(|n| *n != nodes[&root]) {
+            let mut dominated = vec![];
+            let mut stack = vec![n];
+            while let Some(m) = stack.pop() {
+                dominated.push(graph[m]);
+                stack.extend(&children[m.index()]);
+            }
+            let mut only_through : Vec<String> = dominated.iter().filter(|p| **p != graph[n]).map(|p| self.get_package_name(*p).to_string()).collect();
+            only_through.sort();
+            dependencies.push(DependencyWeight {
+                package : self.get_package_name(graph[n]).to_string(),
+                packages : dominated.len(),
+                installed_size_kib : dominated.iter().map(|p| disk_size(*p)).sum(),
+                only_through,
+            });
+        }
+        dependencies.sort_by(|a, b| b.packages.cmp(&a.packages)
+            .then_with(|| b.installed_size_kib.cmp(&a.installed_size_kib))
+            .then_with(|| a.package.cmp(&b.package)));
+        let closure = dependencies.len();
+        if let Some(top) = top {
+            dependencies.truncate(top);
+        }
+        return Ok(Weight {
+            package : package_name.to_string(),
+            closure,
+            installed_size_kib : graph.node_indices().filter(|n| *n != nodes[&root]).map(|n| disk_size(graph[n])).sum(),
+            unknown_sizes : graph.node_indices().filter(|n| *n != nodes[&root] && !self.available_disk_sizes.contains_key(&graph[*n])).count(),
+            dependencies,
+        });
+    }
+}
+
+/// Result of weigh: how much of a package's transitive dependency solution each of its dependencies pulls in alone.
+#[derive(Serialize)]
+pub struct Weight {
+    pub package : String,
+    /// the number of packages in the solution, not counting the package itself
+    pub closure : usize,
+    /// their total Installed-Size
+    pub installed_size_kib : u64,
+    /// how many of them have no Installed-Size, which counts as 0
+    pub unknown_sizes : usize,
+    /// heaviest first; possibly only the first few
+    pub dependencies : Vec<DependencyWeight>,
+}
+
+/// A dependency and the packages that it dominates: the ones that are only reached through it.
+#[derive(Serialize)]
+pub struct DependencyWeight {
+    pub package : String,
+    /// how many packages it dominates, itself included
+    pub packages : usize,
+    pub installed_size_kib : u64,
+    /// the packages it dominates, without itself
+    pub only_through : Vec<String>,
+}
+
+// how many of the packages that a dependency dominates the text form lists
+const WEIGHT_NAMES_SHOWN : usize = 8;
+
+impl Render for Weight {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "Package {}: {} packages in its dependency solution, {} installed",
+            self.package, self.closure, human_size(self.installed_size_kib * 1024))?;
+        if self.unknown_sizes > 0 {
+            writeln!(out, "({} of them have no Installed-Size)", self.unknown_sizes)?;
+        }
+        for d in &self.dependencies {
+            write!(out, "{:>7} {:>10} {}", d.packages, human_size(d.installed_size_kib * 1024), d.package)?;
+            if !d.only_through.is_empty() {
+                write!(out, ": {}", d.only_through.iter().take(WEIGHT_NAMES_SHOWN).cloned().collect::<Vec<_>>().join(", "))?;
+            }
+            if d.only_through.len() > WEIGHT_NAMES_SHOWN {
+                write!(out, " and {} more", d.only_through.len() - WEIGHT_NAMES_SHOWN)?;
+            }
+            writeln!(out)?;
+        }
+        return Ok(());
+    }
+}
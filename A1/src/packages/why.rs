@@ -0,0 +1,165 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::packages::{NoSuchPackage, Render};
+use crate::packages::deps_available::version_satisfies;
+
+// a step from a package to one alternative of one of its Depends or Pre-Depends clauses: (package, clause index as
+// numbered by install_clause, alternative)
+type Step = (i32, usize, i32);
+
+impl Packages {
+    // the steps out of package_num, one per alternative of each Depends and Pre-Depends clause, except those whose
+    // available version doesn't satisfy the clause (unless the installed one does): installing package_num can't pull
+    // them in
+    fn steps_from(&self, package_num: i32) -> Vec<Step> {
+        return self.install_clauses(package_num).enumerate()
+            .flat_map(|(i, dep)| dep.iter()
+                .filter(move |a| !self.wrong_available_version(dep, a.package_num)
+                    || self.installed_debvers.get(&a.package_num).is_some_and(|v| version_satisfies(v, &a.rel_version)))
+                .map(move |a| (package_num, i, a.package_num)))
+            .collect();
+    }
+
+    /// Explains why installing root_name pulls in target_name: the dependency chains from root to target, each step a
+    /// Depends or Pre-Depends clause and an alternative that leads on (one whose available or installed version satisfies the clause).
+    /// With limit 1, that's a shortest chain; with more, the limit shortest distinct chains (that don't go through a
+    /// package twice), shortest first.
+    pub fn why(&self, root_name: &str, target_name: &str, limit: usize) -> Result<WhyReport, NoSuchPackage> {
+        let root = self.find_package_num(root_name).ok_or_else(|| self.no_such_package(root_name))?;
+        let target = self.find_package_num(target_name).ok_or_else(|| self.no_such_package(target_name))?;
+        let mut report = WhyReport { root : root_name.to_string(), target : target_name.to_string(), paths : vec![], limit_reached : false };
+
+        // breadth-first from root: the step that first reached each package, and the reverse edges we saw
+        let mut reached_by : HashMap<i32, Option<Step>> = HashMap::new();
+        let mut reverse : HashMap<i32, Vec<i32>> = HashMap::new();
+        let mut worklist = VecDeque::new();
+        reached_by.insert(root, None);
+        worklist.push_back(root);
+        while let Some(p) = worklist.pop_front() {
+            for step in self.steps_from(p) {
+                reverse.entry(step.2).or_default().push(p);
+                if let Entry::Vacant(e) = reached_by.entry(step.2) {
+                    e.insert(Some(step));
+                    worklist.push_back(step.2);
+                }
+            }
+        }
+        if !reached_by.contains_key(&target) || limit == 0 {
+            return Ok(report);
+        }
+
+        if limit == 1 {
+            let mut path = vec![];
+            let mut p = target;
+            while let Some(Some(step)) = reached_by.get(&p) {
+                path.push(*step);
+                p = step.0;
+            }
+            path.reverse();
+            report.paths.push(self.why_steps(&path));
+            return Ok(report);
+        }
+
+        // how far each package is from target; the search only goes where target can still be reached, closest first
+        let mut distance : HashMap<i32, usize> = HashMap::new();
+        distance.insert(target, 0);
+        let mut worklist = VecDeque::from(vec![target]);
+        while let Some(p) = worklist.pop_front() {
+            for q in reverse.get(&p).map_or(&[][..], |r| &r[..]) {
+                let d = distance[&p] + 1;
+                if let Entry::Vacant(e) = distance.entry(*q) {
+                    e.insert(d);
+                    worklist.push_back(*q);
+                }
+            }
+        }
+        // best-first search over the partial chains from root, by their length plus the distance left to target; since
+        // that distance never overestimates what the chain still needs, complete chains come out shortest first (ties in
+        // the order they were found)
+        let mut chains : Vec<Vec<Step>> = vec![vec![]];
+        let mut queue : BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+        queue.push(Reverse((distance[&root], 0)));
+        while let Some(Reverse((_, c))) = queue.pop() {
+            let chain = std::mem::take(&mut chains[c]);
+            let p = chain.last().map_or(root, |step| step.2);
+            if p == target {
+                report.paths.push(self.why_steps(&chain));
+                if report.paths.len() == limit {
+                    report.limit_reached = !queue.is_empty();
+                    break;
+                }
+                continue;
+            }
+            for step in self.steps_from(p) {
+                let on_chain = step.2 == root || chain.iter().any(|s| s.2 == step.2);
+                if let (Some(d), false) = (distance.get(&step.2), on_chain) {
+                    let mut longer = chain.clone();
+                    longer.push(step);
+                    queue.push(Reverse((longer.len() + d, chains.len())));
+                    chains.push(longer);
+                }
+            }
+        }
+        return Ok(report);
+    }
+
+    fn why_steps(&self, path: &[Step]) -> Vec<WhyStep> {
+        return path.iter().map(|(p, i, a)| WhyStep {
+            package : self.get_package_name(*p).to_string(),
+            dependency : self.dep2str(self.install_clause(*p, *i).0),
+            pre_depends : *i >= self.dependencies[p].len(),
+            chosen : self.get_package_name(*a).to_string(),
+        }).collect();
+    }
+}
+
+/// Result of the why query: dependency chains from root to target.
+#[derive(Serialize)]
+pub struct WhyReport {
+    pub root : String,
+    pub target : String,
+    /// empty if root doesn't (transitively) depend on target
+    pub paths : Vec<Vec<WhyStep>>,
+    /// true if the search stopped at the limit; there may be more chains
+    pub limit_reached : bool,
+}
+
+/// One link of a chain: package's Depends (or, if pre_depends, Pre-Depends) clause dependency, through its alternative
+/// chosen.
+#[derive(Serialize)]
+pub struct WhyStep {
+    pub package : String,
+    pub dependency : String,
+    pub pre_depends : bool,
+    pub chosen : String,
+}
+
+impl Render for WhyReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.paths.is_empty() {
+            return writeln!(out, "{} does not depend on {}", self.root, self.target);
+        }
+        for (n, path) in self.paths.iter().enumerate() {
+            if self.paths.len() > 1 {
+                writeln!(out, "path {} ({} steps):", n + 1, path.len())?;
+            }
+            if path.is_empty() {
+                writeln!(out, "{} is {}", self.root, self.target)?;
+            }
+            for step in path {
+                let relation = if step.pre_depends { "pre-depends on" } else { "depends on" };
+                writeln!(out, "{} {} {:?} -> {}", step.package, relation, step.dependency, step.chosen)?;
+            }
+        }
+        if self.limit_reached {
+            writeln!(out, "(stopped after {} paths; there may be more)", self.paths.len())?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::Packages;
+use crate::debversion::VersionRelation;
+use crate::packages::{NoSuchPackage, Render};
+use crate::packages::deps_available::version_satisfies;
+
+// what we've worked out so far about installing packages
+#[derive(Default)]
+struct Blockers {
+    // package -> whether its dependencies block installing it; its blocking clauses have been reported once already
+    blocked : HashMap<i32, bool>,
+    // packages being looked at; a dependency cycle back to one of them counts as not blocking
+    in_progress : HashSet<i32>,
+}
+
+impl Packages {
+    /// Explains each unsatisfied Depends and Pre-Depends clause of package_name: for every alternative, why it doesn't satisfy the clause
+    /// (no such package, the installed version fails the relation, no loaded version satisfies it, or it just isn't
+    /// installed). An alternative that could be installed is followed through the transitive closure, down to the clauses
+    /// that keep it from being installed; a clause none of whose alternatives can be made to work is impossible.
+    pub fn why_not(&self, package_name: &str) -> Result<WhyNotReport, NoSuchPackage> {
+        let package_num = self.find_package_num(package_name).ok_or_else(|| self.no_such_package(package_name))?;
+        let mut blockers = Blockers::default();
+        blockers.in_progress.insert(package_num);
+        return Ok(WhyNotReport {
+            package : package_name.to_string(),
+            version : self.available_debvers.get(&package_num).map(|v| v.to_string()),
+            unsatisfied : self.explain_unsatisfied(package_num, &mut blockers),
+        });
+    }
+
+    // the clauses of package_num that the installed packages don't satisfy, explained
+    fn explain_unsatisfied(&self, package_num: i32, blockers: &mut Blockers) -> Vec<UnsatisfiedClause> {
+        let mut result = vec![];
+        for (i, dep) in self.install_clauses(package_num).enumerate() {
+            if self.dep_is_satisfied(dep).is_some() {
+                continue;
+            }
+            let alternatives : Vec<AlternativeStatus> = dep.iter()
+                .map(|a| self.explain_alternative(a.package_num, &a.rel_version, blockers))
+                .collect();
+            result.push(UnsatisfiedClause {
+                package : self.get_package_name(package_num).to_string(),
+                dependency : self.dep2str(dep),
+                pre_depends : i >= self.dependencies[&package_num].len(),
+                impossible : !alternatives.iter().any(|a| a.usable),
+                alternatives,
+            });
+        }
+        return result;
+    }
+
+    fn explain_alternative(&self, package_num: i32, rel_version: &Option<(VersionRelation, String)>, blockers: &mut Blockers) -> AlternativeStatus {
+        let required = rel_version.as_ref().map_or(String::new(), |(rel, ver)| format!("{} {}", rel, ver));
+        let available = self.available_debvers.get(&package_num);
+        let installable = available.filter(|v| version_satisfies(v, rel_version)).map(|v| v.to_string());
+        let cause = match (self.installed_debvers.get(&package_num), available) {
+            (None, None) => AlternativeCause::NoSuchPackage,
+            (Some(installed), _) => AlternativeCause::WrongInstalledVersion { installed : installed.to_string(), required, available : installable.clone() },
+            (None, Some(available)) => match &installable {
+                Some(v) => AlternativeCause::NotInstalled { available : v.clone() },
+                None => AlternativeCause::VersionNotAvailable { available : available.to_string(), required }
+            }
+        };
+
+        let mut blocked_by = vec![];
+        let mut usable = installable.is_some();
+        if usable {
+            if let Some(blocked) = blockers.blocked.get(&package_num) {
+                usable = !blocked;
+            } else if blockers.in_progress.insert(package_num) {
+                blocked_by = self.explain_unsatisfied(package_num, blockers);
+                // only what keeps it from being installed is worth showing
+                blocked_by.retain(|clause| clause.impossible);
+                usable = blocked_by.is_empty();
+                blockers.in_progress.remove(&package_num);
+                blockers.blocked.insert(package_num, !usable);
+            }
+        }
+        return AlternativeStatus { package : self.get_package_name(package_num).to_string(), cause, usable, blocked_by };
+    }
+}
+
+/// Result of the why-not query: the unsatisfied Depends and Pre-Depends clauses of a package, and why.
+#[derive(Serialize)]
+pub struct WhyNotReport {
+    pub package : String,
+    /// the available version, whose dependencies these are; None if the package isn't in the Packages index
+    pub version : Option<String>,
+    pub unsatisfied : Vec<UnsatisfiedClause>,
+}
+
+#[derive(Serialize)]
+pub struct UnsatisfiedClause {
+    /// the package the clause belongs to
+    pub package : String,
+    pub dependency : String,
+    /// true for a Pre-Depends clause
+    pub pre_depends : bool,
+    /// true if no alternative can be made to satisfy the clause, even by installing or upgrading packages
+    pub impossible : bool,
+    pub alternatives : Vec<AlternativeStatus>,
+}
+
+#[derive(Serialize)]
+pub struct AlternativeStatus {
+    pub package : String,
+    #[serde(flatten)]
+    pub cause : AlternativeCause,
+    /// true if installing (or upgrading to) the available version would satisfy the clause
+    pub usable : bool,
+    /// the impossible clauses that keep the available version from being installed; empty if they're listed
+    /// elsewhere in the report
+    pub blocked_by : Vec<UnsatisfiedClause>,
+}
+
+/// Why an alternative doesn't satisfy a clause.
+#[derive(Serialize)]
+#[serde(tag = "cause", rename_all = "snake_case")]
+pub enum AlternativeCause {
+    /// neither installed nor available
+    NoSuchPackage,
+    /// the installed version fails the relation; available is the available version if it would do
+    WrongInstalledVersion { installed : String, required : String, available : Option<String> },
+    /// not installed, and the available version fails the relation
+    VersionNotAvailable { available : String, required : String },
+    /// not installed, but the available version would do
+    NotInstalled { available : String },
+}
+
+impl AlternativeCause {
+    /// True if installing the available version would satisfy the clause (were it not for its own dependencies).
+    pub fn available_would_do(&self) -> bool {
+        return matches!(self, AlternativeCause::NotInstalled { .. } | AlternativeCause::WrongInstalledVersion { available : Some(_), .. });
+    }
+}
+
+fn render_unsatisfied(out: &mut impl Write, clause: &UnsatisfiedClause, indent: usize) -> io::Result<()> {
+    let pad = " ".repeat(indent);
+    let relation = if clause.pre_depends { "pre-depends on" } else { "depends on" };
+    writeln!(out, "{}{} {} {:?}, which is not satisfied{}:", pad, clause.package, relation, clause.dependency,
+        if clause.impossible { " and can't be" } else { "" })?;
+    for a in &clause.alternatives {
+        let why = match &a.cause {
+            AlternativeCause::NoSuchPackage => String::from("no such package"),
+            AlternativeCause::WrongInstalledVersion { installed, required, available : Some(available) } =>
+                format!("installed version {} is not {}; upgrading to {} would do", installed, required, available),
+            AlternativeCause::WrongInstalledVersion { installed, required, available : None } =>
+                format!("installed version {} is not {}, and no loaded version is", installed, required),
+            AlternativeCause::VersionNotAvailable { available, required } =>
+                format!("not installed; available version {} is not {}", available, required),
+            AlternativeCause::NotInstalled { available } => format!("not installed; available version {} would do", available),
+        };
+        let blocked = if a.usable || !a.cause.available_would_do() {
+            ""
+        } else if a.blocked_by.is_empty() {
+            ", but it can't be installed (see above)"
+        } else {
+            ", but it can't be installed:"
+        };
+        writeln!(out, "{}  - {}: {}{}", pad, a.package, why, blocked)?;
+        for c in &a.blocked_by {
+            render_unsatisfied(out, c, indent + 4)?;
+        }
+    }
+    return Ok(());
+}
+
+impl Render for WhyNotReport {
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        match &self.version {
+            Some(version) => writeln!(out, "Package {} {}:", self.package, version)?,
+            None => writeln!(out, "Package {} (not in the Packages index):", self.package)?
+        }
+        if self.unsatisfied.is_empty() {
+            return writeln!(out, "all dependencies are satisfied");
+        }
+        for clause in &self.unsatisfied {
+            render_unsatisfied(out, clause, 0)?;
+        }
+        return Ok(());
+    }
+}
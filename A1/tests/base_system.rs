@@ -0,0 +1,25 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::HighestVersion;
+
+#[test]
+fn required_packages_are_closed_over_their_pre_dependencies() {
+    let base = fixtures().base_system(false, &HighestVersion);
+    assert_eq!(base.seeds.len(), 13);
+    assert!(base.dependencies.is_empty());
+    assert!(base.unsatisfiable.is_empty());
+    let steps : Vec<String> = base.order.steps.iter().map(|s| format!("{:?} {}", s.action, s.package)).collect();
+    let at = |step: &str| steps.iter().position(|s| s == step).unwrap();
+    assert!(at("Configure libtinfo6") < at("Unpack bash"));
+}
+
+#[test]
+fn pre_dependencies_of_important_packages_are_pulled_in() {
+    let base = fixtures().base_system(true, &HighestVersion);
+    assert!(base.seeds.contains(&"pd-app".to_string()));
+    assert_eq!(base.dependencies, vec!["pd-lib", "pd-pre"]);
+    assert_eq!(base.unsatisfiable, vec![r#"pd-lib pre-depends on "pd-missing": pd-missing is not available"#]);
+}
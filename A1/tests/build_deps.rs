@@ -0,0 +1,64 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{fixture_path, fixtures};
+use rpkg::Packages;
+use rpkg::packages::{BuildAlternative, FirstListed, RelVersionedPackageNum};
+
+fn with_sources() -> Packages {
+    let mut packages = fixtures();
+    packages.parse_sources(&fixture_path("Sources")).unwrap();
+    return packages;
+}
+
+fn applies_on(architectures: &str, arch: &str) -> bool {
+    let alternative = BuildAlternative {
+        dependency : RelVersionedPackageNum { package_num : 0, rel_version : None },
+        architectures : architectures.split(' ').map(String::from).collect(),
+    };
+    return alternative.applies_on(arch);
+}
+
+#[test]
+fn wildcards_match_architecture_tuples() {
+    assert!(applies_on("any-arm", "armel") && applies_on("any-arm", "armhf") && applies_on("any-arm", "hurd-arm"));
+    assert!(!applies_on("any-arm", "arm64"));
+    assert!(applies_on("linux-any", "amd64") && applies_on("linux-any", "armhf") && applies_on("linux-any", "x32"));
+    assert!(!applies_on("linux-any", "hurd-i386") && !applies_on("linux-any", "kfreebsd-amd64"));
+    assert!(applies_on("any-amd64", "kfreebsd-amd64") && applies_on("any-amd64", "x32"));
+    assert!(applies_on("gnu-any-any", "hurd-i386") && !applies_on("gnu-any-any", "freebsd-amd64"));
+    assert!(applies_on("eabihf-any-any-arm", "armhf") && !applies_on("eabihf-any-any-arm", "armel"));
+    assert!(applies_on("!hurd-any !kfreebsd-any", "mips64el") && !applies_on("!hurd-any", "hurd-amd64"));
+    assert!(!applies_on("armhf", "not-an-arch") && !applies_on("any-arm", "not-an-arch"));
+}
+
+#[test]
+fn clauses_are_kept_by_architecture() {
+    let packages = with_sources();
+    let clauses = |arch: &str| packages.build_deps("threedepict", arch, true, true, &FirstListed).unwrap()
+        .dependencies.into_iter().map(|d| d.dependency).collect::<Vec<_>>();
+    let amd64 = clauses("amd64");
+    assert!(!amd64.contains(&"libhurd-dev".to_string()) && !amd64.contains(&"libneon-dev".to_string()));
+    assert!(clauses("armhf").contains(&"libneon-dev".to_string()));
+    assert!(clauses("hurd-i386").contains(&"libhurd-dev".to_string()));
+}
+
+#[test]
+fn arch_only_builds_follow_negated_profiles() {
+    // check is only restricted to builds without the nocheck profile, which is every build here
+    let build = with_sources().build_deps("threedepict", "amd64", true, false, &FirstListed).unwrap();
+    assert_eq!(build.fields, vec!["Build-Depends", "Build-Depends-Arch", "Build-Conflicts"]);
+    assert_eq!(build.to_install, vec!["libftgl2", "check", "gawk", "libfreetype6", "libmpfr6"]);
+    assert_eq!(build.to_remove.iter().map(|r| &r.package[..]).collect::<Vec<_>>(), vec!["mawk"]);
+    assert!(build.unsatisfiable.is_empty());
+}
+
+#[test]
+fn unknown_sources_get_suggestions() {
+    let packages = with_sources();
+    // a binary package name leads to its source, and a misspelt source name to the closest one
+    assert_eq!(packages.build_deps("3depict", "amd64", true, true, &FirstListed).err().unwrap().suggestions, vec!["threedepict"]);
+    assert_eq!(packages.build_deps("threedpeict", "amd64", true, true, &FirstListed).err().unwrap().suggestions, vec!["threedepict"]);
+    assert!(packages.build_deps("zzzzzzzz", "amd64", true, true, &FirstListed).err().unwrap().suggestions.is_empty());
+}
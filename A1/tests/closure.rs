@@ -0,0 +1,30 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+
+#[test]
+fn the_engine_agrees_with_transitive_dep_solution() {
+    let packages = fixtures();
+    let sizes = packages.closure_engine().closure_sizes();
+    let mut engine = packages.closure_engine();
+    for name in packages.get_package_names() {
+        let p = packages.find_package_num(name).unwrap();
+        let mut expected = packages.transitive_dep_solution(name);
+        expected.sort();
+        assert_eq!(sizes[p as usize], expected.len(), "closure size of {}", name);
+        assert_eq!(engine.closure(p), expected, "closure of {}", name);
+    }
+}
+
+#[test]
+fn closure_sizes_lists_the_largest_first() {
+    let packages = fixtures();
+    let available = packages.get_package_names().into_iter().filter(|n| packages.get_available_debver(n).is_some()).count();
+    let report = packages.closure_sizes(Some(3));
+    assert_eq!(report.packages, available);
+    assert_eq!(report.largest.iter().map(|c| (&c.package[..], c.size)).collect::<Vec<_>>(), vec![("3depict", 13), ("cyc-a", 8), ("cyc-b", 8)]);
+    assert_eq!(packages.closure_sizes(None).largest.len(), available);
+}
+
@@ -0,0 +1,16 @@
+// The made-up indexes in tests/fixtures, which the README examples also use. Each test crate uses some of these.
+#![allow(dead_code)]
+
+use rpkg::Packages;
+
+pub fn fixture_path(name: &str) -> String {
+    return format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+}
+
+/// The fixture Packages index and installed packages, loaded.
+pub fn fixtures() -> Packages {
+    let mut packages = Packages::new();
+    packages.parse_packages(&fixture_path("Packages")).unwrap();
+    packages.parse_installed(&fixture_path("installed-packages")).unwrap();
+    return packages;
+}
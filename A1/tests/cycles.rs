@@ -0,0 +1,37 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::DependencyCycle;
+
+// whether the cycle's edges that don't close it leave its members acyclic
+fn acyclic_without_closers(cycle: &DependencyCycle) -> bool {
+    let mut left : Vec<&str> = cycle.members.iter().map(|m| &m[..]).collect();
+    let edges : Vec<(&str, &str)> = cycle.edges.iter().filter(|e| !e.closes_cycle).map(|e| (&e.from[..], &e.to[..])).collect();
+    // repeatedly take out a member with no edge to a member still left
+    while let Some(i) = left.iter().position(|m| !edges.iter().any(|(from, to)| from == m && left.contains(to))) {
+        left.remove(i);
+    }
+    return left.is_empty();
+}
+
+#[test]
+fn cycles_reachable_from_a_package() {
+    let report = fixtures().cycles(false, Some("cyc-a")).unwrap();
+    assert_eq!(report.packages, 8);
+    let members : Vec<Vec<&str>> = report.cycles.iter().map(|c| c.members.iter().map(|m| &m[..]).collect()).collect();
+    assert_eq!(members, vec![vec!["cyc-a", "cyc-b", "cyc-c"], vec!["libc6", "libcrypt1", "libgcc-s1"]]);
+    let pre_depends : Vec<(&str, &str)> = report.cycles[0].edges.iter().filter(|e| e.pre_depends).map(|e| (&e.from[..], &e.to[..])).collect();
+    assert_eq!(pre_depends, vec![("cyc-b", "cyc-a")]);
+    assert!(report.cycles.iter().all(acyclic_without_closers));
+    assert!(report.cycles.iter().all(|c| c.edges.iter().any(|e| e.closes_cycle)));
+}
+
+#[test]
+fn installed_cycles_only_involve_installed_packages() {
+    let report = fixtures().cycles(true, None).unwrap();
+    assert_eq!(report.cycles.len(), 1);
+    assert_eq!(report.cycles[0].members, vec!["libc6", "libcrypt1", "libgcc-s1"]);
+    assert!(fixtures().cycles(false, Some("no-such-package")).is_err());
+}
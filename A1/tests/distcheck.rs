@@ -0,0 +1,26 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+
+#[test]
+fn pre_dependencies_must_be_satisfiable() {
+    let report = fixtures().distcheck(2);
+    let broken = |name: &str| report.broken.iter().find(|b| b.package == name);
+    let pd_lib = broken("pd-lib").unwrap();
+    assert_eq!(pd_lib.clause.as_deref(), Some("pd-lib pre-depends on \"pd-missing\""));
+    assert_eq!(pd_lib.reasons, vec!["pd-missing is not available"]);
+    assert_eq!(broken("pd-app").unwrap().via, vec!["pd-app", "pd-lib"]);
+    assert!(broken("pd-pre").is_none());
+    assert!(broken("cyc-a").is_none());
+}
+
+#[test]
+fn conflicts_need_the_sat_solver() {
+    let report = fixtures().distcheck(1);
+    let cf_bad = report.broken.iter().find(|b| b.package == "cf-bad").unwrap();
+    assert_eq!(cf_bad.clause, None);
+    assert!(cf_bad.reasons.iter().any(|r| r.starts_with("cf-c breaks cf-a (<< 2.0)")));
+    assert_eq!(report.checked, report.installable + report.broken.len());
+}
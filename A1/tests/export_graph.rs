@@ -0,0 +1,30 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::{DepGraphNode, PackageStatus};
+
+#[test]
+fn nodes_are_colored_by_status() {
+    let packages = fixtures();
+    let graph = packages.dependency_graph("bt-root", Some(2)).unwrap();
+    let status = |name: &str| graph.node_indices().find_map(|n| match graph[n] {
+        DepGraphNode::Package(p) if packages.get_package_name(p) == name => Some(packages.package_status(p)),
+        _ => None
+    });
+    assert_eq!(status("bt-x"), Some(PackageStatus::Available));
+    assert_eq!(status("nothing-here"), Some(PackageStatus::Missing));
+    assert_eq!(status("vc-lib"), Some(PackageStatus::Installed));
+
+    let mut dot = vec![];
+    packages.write_dot(&graph, &mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("[label=\"nothing-here\", shape=box, fillcolor=lightcoral]"));
+    assert!(dot.contains("shape=diamond"));
+
+    let mut graphml = vec![];
+    packages.write_graphml(&graph, &mut graphml).unwrap();
+    let graphml = String::from_utf8(graphml).unwrap();
+    assert!(graphml.contains("<data key=\"label\">nothing-here</data><data key=\"kind\">package</data><data key=\"status\">missing</data><data key=\"color\">#f08080</data>"));
+}
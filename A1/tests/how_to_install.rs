@@ -0,0 +1,113 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::Packages;
+use rpkg::packages::{parse_install_requests, HighestVersion, InstallPlan, SmallestClosure, SoftDependencies};
+
+fn plan(packages: &Packages, request: &str, soft: SoftDependencies) -> InstallPlan {
+    let words : Vec<&str> = request.split(' ').collect();
+    return packages.how_to_install(&parse_install_requests(&words).unwrap(), &HighestVersion, soft).unwrap();
+}
+
+fn removed(plan: &InstallPlan) -> Vec<&str> {
+    return plan.to_remove.iter().map(|r| &r.package[..]).collect();
+}
+
+#[test]
+fn removes_what_the_chosen_alternative_conflicts_with() {
+    let plan = plan(&fixtures(), "needs-gawk", SoftDependencies::default());
+    assert_eq!(plan.to_install, vec!["gawk", "libmpfr6"]);
+    assert_eq!(removed(&plan), vec!["mawk"]);
+    assert!(plan.unsatisfiable.is_empty());
+}
+
+#[test]
+fn skips_alternatives_whose_available_version_is_too_old() {
+    // pv-lib (>= 2.0) comes first, but only 1.5 is available; pv-alt will do, at the cost of a removal
+    let plan = plan(&fixtures(), "pv-app", SoftDependencies::default());
+    assert_eq!(plan.to_install, vec!["pv-alt"]);
+    assert_eq!(removed(&plan), vec!["oldtool"]);
+    assert!(plan.unsatisfiable.is_empty());
+}
+
+#[test]
+fn a_requested_package_of_the_wrong_version_doesnt_satisfy_a_dependency() {
+    // pv-lib 1.5 is requested as well, but pv-app needs pv-lib (>= 2.0)
+    let plan = plan(&fixtures(), "pv-lib pv-app", SoftDependencies::default());
+    assert_eq!(plan.to_install, vec!["pv-alt"]);
+    assert_eq!(removed(&plan), vec!["oldtool"]);
+    assert!(plan.unsatisfiable.is_empty());
+}
+
+#[test]
+fn reports_a_dependency_that_no_available_version_satisfies() {
+    let plan = plan(&fixtures(), "pv-stuck", SoftDependencies::default());
+    assert!(plan.to_install.is_empty());
+    assert_eq!(plan.unsatisfiable, vec![r#"pv-stuck depends on "pv-lib (>= 2.0)": pv-lib 1.5 is available, which doesn't satisfy it"#]);
+}
+
+#[test]
+fn passes_over_alternatives_whose_dependencies_cant_be_met() {
+    // bt-root depends on "bt-x | bt-y", and bt-x depends on nothing-here, which isn't available
+    let plan = plan(&fixtures(), "bt-root", SoftDependencies::default());
+    assert_eq!(plan.to_install, vec!["bt-z", "bt-y", "vc-lib"]);
+    assert!(plan.unsatisfiable.is_empty());
+}
+
+#[test]
+fn reports_dependencies_on_packages_that_arent_available() {
+    let plan = plan(&fixtures(), "pd-app", SoftDependencies::default());
+    assert_eq!(plan.to_install, vec!["pd-lib", "pd-pre"]);
+    assert_eq!(plan.unsatisfiable, vec![r#"pd-lib pre-depends on "pd-missing": pd-missing is not available"#]);
+}
+
+#[test]
+fn combines_several_requests() {
+    let plan = plan(&fixtures(), "needs-gawk 3depict bash (>= 5.0)", SoftDependencies::default());
+    assert_eq!(plan.to_install, vec!["gawk", "libftgl2", "libmpfr6", "libfreetype6"]);
+    assert_eq!(plan.requested_by["libfreetype6"], "3depict");
+    assert_eq!(plan.already_satisfied, vec!["bash (>= 5.0)"]);
+}
+
+#[test]
+fn follows_recommends_that_can_be_installed_cleanly() {
+    let plan = plan(&fixtures(), "app", SoftDependencies { recommends : true, suggests : false });
+    assert_eq!(plan.to_install, vec!["helper", "helper-lib", "rec-alt", "helper-extra"]);
+    assert_eq!(plan.skipped.len(), 3);
+    assert!(plan.skipped[2].ends_with("rec-old 1.0 is available, which doesn't satisfy it"));
+}
+
+#[test]
+fn policy_picks_among_alternatives() {
+    let packages = fixtures();
+    let requests = parse_install_requests(&["bt-root"]).unwrap();
+    let plan = packages.how_to_install(&requests, &SmallestClosure, SoftDependencies::default()).unwrap();
+    assert_eq!(plan.to_install, vec!["bt-z", "bt-y", "vc-lib"]);
+}
+
+#[test]
+fn recommends_that_dont_work_out_leave_no_trace() {
+    let packages = fixtures();
+    let app = packages.find_package_num("app").unwrap();
+    let plan = packages.compute_install_plan_for(&[app], &HighestVersion, SoftDependencies { recommends : true, suggests : true });
+    let names = |nums: &mut dyn Iterator<Item = &i32>| nums.map(|p| packages.get_package_name(*p)).collect::<Vec<_>>();
+    assert_eq!(names(&mut plan.install.iter()), vec!["helper", "helper-lib", "rec-alt", "sugg", "helper-extra"]);
+    let mut requested : Vec<&str> = names(&mut plan.requested_by.keys());
+    requested.sort();
+    assert_eq!(requested, vec!["app", "helper", "helper-extra", "helper-lib", "rec-alt", "sugg"]);
+    assert!(plan.remove.is_empty() && plan.unsatisfiable.is_empty());
+    assert_eq!(plan.skipped.len(), 3);
+}
+
+#[test]
+fn adds_up_download_and_disk_space() {
+    // needs-gawk itself, gawk and libmpfr6 take their Installed-Size, and removing mawk gives its own back
+    let plan = plan(&fixtures(), "needs-gawk", SoftDependencies::default());
+    assert_eq!(plan.sizes.download_bytes, 1000 + 620000 + 340000);
+    assert_eq!(plan.sizes.disk_change_kib, 10 + 2600 + 1400 - 230);
+    let by_package : Vec<(&str, Option<i64>)> = plan.sizes.packages.iter().map(|p| (&p.package[..], p.disk_change_kib)).collect();
+    assert_eq!(by_package, vec![("gawk", Some(2600)), ("libmpfr6", Some(1400)), ("needs-gawk", Some(10)), ("mawk", Some(-230))]);
+    assert!(plan.sizes.unknown.is_empty());
+}
@@ -0,0 +1,46 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::{InstallOrder, StepAction};
+
+fn steps(order: &InstallOrder) -> Vec<(StepAction, &str)> {
+    return order.steps.iter().map(|s| (s.action, &s.package[..])).collect();
+}
+
+#[test]
+fn pre_dependencies_are_planned_and_configured_before_unpacking() {
+    let order = fixtures().install_order("pd-app").unwrap();
+    let steps = steps(&order);
+    let at = |action, package| steps.iter().position(|s| *s == (action, package)).unwrap();
+    assert!(at(StepAction::Configure, "pd-pre") < at(StepAction::Unpack, "pd-app"));
+    assert!(at(StepAction::Unpack, "pd-lib") < at(StepAction::Configure, "pd-app"));
+    assert_eq!(order.problems, vec![r#"pd-lib pre-depends on "pd-missing": pd-missing is not available"#]);
+}
+
+#[test]
+fn packages_that_arent_available_are_only_reported() {
+    let order = fixtures().install_order("nothing-here").unwrap();
+    assert!(order.steps.is_empty());
+    assert_eq!(order.problems, vec!["nothing-here is not available"]);
+
+    let order = fixtures().install_order("bt-x").unwrap();
+    assert!(!order.steps.iter().any(|s| s.package == "nothing-here"));
+    assert_eq!(order.problems, vec![r#"bt-x depends on "nothing-here": nothing-here is not available"#]);
+}
+
+#[test]
+fn cycles_are_unpacked_together_honoring_pre_dependencies() {
+    let order = fixtures().install_order("cyc-a").unwrap();
+    assert_eq!(steps(&order), vec![
+        (StepAction::Unpack, "cyc-a"),
+        (StepAction::Configure, "cyc-a"),
+        (StepAction::Unpack, "cyc-b"),
+        (StepAction::Unpack, "cyc-c"),
+        (StepAction::Configure, "cyc-b"),
+        (StepAction::Configure, "cyc-c"),
+    ]);
+    assert_eq!(order.cycles.len(), 1);
+    assert!(order.problems.is_empty());
+}
@@ -0,0 +1,41 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{fixture_path, fixtures};
+use rpkg::packages::{parse_install_requests, read_install_requests, HighestVersion, InstallPlan, SoftDependencies};
+
+fn plan(request: &str) -> InstallPlan {
+    let words : Vec<&str> = request.split(' ').collect();
+    return fixtures().how_to_install(&parse_install_requests(&words).unwrap(), &HighestVersion, SoftDependencies::default()).unwrap();
+}
+
+#[test]
+fn words_are_grouped_into_requests() {
+    let requests = parse_install_requests(&["a", "b", "(>=", "1.0)", "c", "(<<", "2)"]).unwrap();
+    assert_eq!(requests.iter().map(|r| r.to_string()).collect::<Vec<_>>(), vec!["a", "b (>= 1.0)", "c (<< 2)"]);
+    assert!(parse_install_requests(&["a", "(~", "1)"]).is_err());
+}
+
+#[test]
+fn requests_are_read_from_a_file() {
+    let requests = read_install_requests(&fixture_path("requests")).unwrap();
+    assert_eq!(requests.iter().map(|r| r.to_string()).collect::<Vec<_>>(), vec!["needs-gawk", "3depict", "bash (>= 5.0)"]);
+    assert!(read_install_requests(&fixture_path("no-such-file")).err().unwrap().starts_with("couldn't read"));
+}
+
+#[test]
+fn requests_that_cant_hold_are_unsatisfiable() {
+    let plan = plan("wn-old (>= 2.0)");
+    assert!(plan.to_install.is_empty());
+    assert_eq!(plan.unsatisfiable, vec!["requested wn-old (>= 2.0), but the available version is 1.5"]);
+}
+
+#[test]
+fn a_request_that_conflicts_with_another_is_reported_and_planned_around() {
+    let plan = plan("gawk mawk");
+    assert_eq!(plan.to_install, vec!["libmpfr6"]);
+    assert_eq!(plan.requested_by["libmpfr6"], "gawk");
+    assert_eq!(plan.to_remove.iter().map(|r| &r.package[..]).collect::<Vec<_>>(), vec!["mawk"]);
+    assert_eq!(plan.unsatisfiable, vec!["gawk conflicts with mawk (both requested)"]);
+}
@@ -0,0 +1,67 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use serde_json::Value;
+
+use common::fixtures;
+use rpkg::packages::{parse_install_requests, HighestVersion, OutputFormat, Render, SoftDependencies};
+
+// report as the REPL prints it in JSON mode, parsed back
+fn json(report: &impl Render) -> Value {
+    let mut out = vec![];
+    report.render_as(OutputFormat::Json, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(text.lines().count(), 1, "not one line: {}", text);
+    return serde_json::from_str(&text).unwrap();
+}
+
+// the field names of an object, sorted
+fn fields(value: &Value) -> Vec<&str> {
+    let mut names : Vec<&str> = value.as_object().unwrap().keys().map(|k| &k[..]).collect();
+    names.sort();
+    return names;
+}
+
+#[test]
+fn info_documents_have_the_documented_fields() {
+    let info = json(&fixtures().info("bash").unwrap());
+    assert_eq!(fields(&info), vec!["depends", "installed_version", "md5sum", "newer_available", "package", "version"]);
+    assert_eq!(info["package"], "bash");
+    assert_eq!(info["depends"][0], "base-files (>= 2.1.12)");
+    assert_eq!(info["newer_available"], true);
+    // unknown fields are null, not left out
+    let info = json(&fixtures().info("needs-gawk").unwrap());
+    assert!(info["installed_version"].is_null());
+}
+
+#[test]
+fn deps_available_documents_have_the_documented_fields() {
+    let report = json(&fixtures().deps_available("bash").unwrap());
+    assert_eq!(fields(&report), vec!["dependencies", "package"]);
+    let dependency = &report["dependencies"][0];
+    assert_eq!(fields(dependency), vec!["dependency", "satisfied_by"]);
+    assert_eq!(fields(&dependency["satisfied_by"]), vec!["installed_version", "package"]);
+    assert_eq!(dependency["satisfied_by"]["package"], "base-files");
+}
+
+#[test]
+fn how_to_install_documents_have_the_documented_fields() {
+    let requests = parse_install_requests(&["needs-gawk"]).unwrap();
+    let plan = json(&fixtures().how_to_install(&requests, &HighestVersion, SoftDependencies::default()).unwrap());
+    assert_eq!(fields(&plan), vec!["already_satisfied", "packages", "requested_by", "sizes", "skipped", "soft_dependencies",
+        "to_install", "to_remove", "unsatisfiable"]);
+    assert_eq!(plan["requested_by"]["gawk"], "needs-gawk");
+    assert_eq!(fields(&plan["to_remove"][0]), vec!["installed_version", "package", "reason"]);
+    assert_eq!(fields(&plan["sizes"]), vec!["disk_change_kib", "download_bytes", "packages", "unknown"]);
+    assert_eq!(fields(&plan["sizes"]["packages"][0]), vec!["disk_change_kib", "download_bytes", "package"]);
+}
+
+#[test]
+fn unknown_packages_are_reported_as_errors() {
+    let error = json(&fixtures().info("bsah").err().unwrap());
+    assert_eq!(fields(&error), vec!["error", "package", "suggestions"]);
+    assert_eq!(error["error"], "no_such_package");
+    assert_eq!(error["package"], "bsah");
+    assert_eq!(error["suggestions"][0], "bash");
+}
@@ -0,0 +1,34 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::{UpgradableOptions, UpgradableReport, UpgradableSort, UpgradeKind};
+
+fn listed(report: &UpgradableReport) -> Vec<(&str, Option<&str>, UpgradeKind)> {
+    return report.packages.iter().map(|p| (&p.package[..], p.section.as_deref(), p.kind)).collect();
+}
+
+#[test]
+fn lists_upgrades_downgrades_and_installed_only_packages() {
+    let report = fixtures().list_upgradable(&UpgradableOptions { sort : UpgradableSort::Kind, sections : vec![] });
+    assert_eq!(listed(&report), vec![
+        ("bash", Some("shells"), UpgradeKind::Upgrade),
+        ("kp-lib", Some("libs"), UpgradeKind::Upgrade),
+        ("oc-user", Some("misc"), UpgradeKind::Upgrade),
+        ("vc-lib", Some("libs"), UpgradeKind::Upgrade),
+        ("liblzma5", Some("libs"), UpgradeKind::Downgrade),
+        ("localonly", Some("utils"), UpgradeKind::InstalledOnly),
+    ]);
+    assert_eq!((report.upgrades, report.downgrades, report.installed_only), (4, 1, 1));
+}
+
+#[test]
+fn section_filter_keeps_installed_only_packages_by_their_installed_section() {
+    let sections = vec!["utils".to_string(), "shells".to_string()];
+    let report = fixtures().list_upgradable(&UpgradableOptions { sort : UpgradableSort::Section, sections });
+    assert_eq!(listed(&report), vec![
+        ("bash", Some("shells"), UpgradeKind::Upgrade),
+        ("localonly", Some("utils"), UpgradeKind::InstalledOnly),
+    ]);
+}
@@ -0,0 +1,27 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{fixture_path, fixtures};
+use rpkg::packages::{HighestVersion, SoftDependencies};
+
+#[test]
+fn a_new_stanza_replaces_every_field_of_the_old_one() {
+    let mut packages = fixtures();
+    packages.parse_packages(&fixture_path("Packages-update")).unwrap();
+
+    let app = packages.find_package_num("app").unwrap();
+    let plan = packages.compute_install_plan_for(&[app], &HighestVersion, SoftDependencies { recommends : true, suggests : true });
+    assert!(plan.install.is_empty() && plan.soft.is_empty() && plan.skipped.is_empty());
+    assert_eq!(packages.get_section("app"), None);
+
+    let order = packages.install_order("pd-app").unwrap();
+    assert!(order.steps.iter().all(|s| s.package != "pd-pre"));
+    assert!(!packages.base_system(true, &HighestVersion).seeds.contains(&"pd-app".to_string()));
+
+    let info = packages.info("3depict").unwrap();
+    assert_eq!((info.version.as_deref(), info.md5sum), (Some("0.0.23-2"), None));
+    assert_eq!(info.depends, Some(vec!["libc6 (>= 2.33)".to_string()]));
+    let sizes = packages.size_summary(&[packages.find_package_num("3depict").unwrap()], &[], true);
+    assert_eq!(sizes.unknown, vec!["3depict"]);
+}
@@ -0,0 +1,40 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+
+#[test]
+fn removal_breaks_dependents_transitively() {
+    let impact = fixtures().plan_remove(&["libstdc++6"]).unwrap();
+    let broken : Vec<(&str, &str)> = impact.broken.iter().map(|b| (&b.package[..], &b.removed[..])).collect();
+    assert_eq!(broken, vec![("libicu67", "libstdc++6"), ("libxml2", "libicu67")]);
+    assert_eq!(impact.broken[0].dependency, "libstdc++6 (>= 5.2)");
+    assert_eq!(impact.orphaned.iter().map(|o| &o.package[..]).collect::<Vec<_>>(), vec!["liblzma5"]);
+}
+
+#[test]
+fn alternatives_that_are_all_removed_break_the_dependency() {
+    let impact = fixtures().plan_remove(&["libc6"]).unwrap();
+    let awk = impact.broken.iter().find(|b| b.package == "awk").unwrap();
+    assert_eq!((&awk.dependency[..], &awk.removed[..]), ("mawk | gawk", "mawk"));
+    assert!(impact.still_satisfied.is_empty());
+    assert_eq!(impact.broken.len(), 15);
+}
+
+#[test]
+fn what_only_removed_packages_needed_is_orphaned() {
+    let impact = fixtures().plan_remove(&["kb-user", "nothing-here"]).unwrap();
+    assert_eq!(impact.not_installed, vec!["nothing-here"]);
+    assert!(impact.broken.is_empty());
+    assert_eq!(impact.orphaned.iter().map(|o| (&o.package[..], &o.installed_version[..])).collect::<Vec<_>>(), vec![("vc-lib", "1.0")]);
+}
+
+#[test]
+fn pre_dependencies_break_too() {
+    // bash pre-depends on "libc6 (>= 2.25), libtinfo6 (>= 6)"
+    let impact = fixtures().plan_remove(&["libtinfo6"]).unwrap();
+    let broken : Vec<(&str, &str, bool)> = impact.broken.iter().map(|b| (&b.package[..], &b.dependency[..], b.pre_depends)).collect();
+    assert_eq!(broken, vec![("bash", "libtinfo6 (>= 6)", true)]);
+    assert_eq!(impact.orphaned.iter().map(|o| &o.package[..]).collect::<Vec<_>>(), vec!["base-files", "debianutils"]);
+}
@@ -0,0 +1,38 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::UpgradePlan;
+
+fn upgrades(plan: &UpgradePlan) -> Vec<(&str, &str, &str)> {
+    return plan.upgrade.iter().map(|c| (&c.package[..], &c.from[..], &c.to[..])).collect();
+}
+
+#[test]
+fn upgrade_keeps_back_what_needs_new_packages_or_breaks_a_dependency() {
+    let plan = fixtures().plan_upgrade(false);
+    assert_eq!(upgrades(&plan), vec![("bash", "5.1-5+b1", "5.1-6")]);
+    assert!(plan.install.is_empty() && plan.remove.is_empty());
+    let kept_back : Vec<&str> = plan.kept_back.iter().map(|k| &k.package[..]).collect();
+    assert_eq!(kept_back, vec!["kp-lib", "oc-user", "vc-lib"]);
+    assert_eq!(plan.kept_back[2].reason, "kb-user 1.0 depends on \"vc-lib (<< 2.0)\", which wouldn't be satisfied");
+}
+
+#[test]
+fn dist_upgrade_installs_and_removes_but_never_downgrades() {
+    let plan = fixtures().plan_upgrade(true);
+    assert_eq!(upgrades(&plan), vec![("bash", "5.1-5+b1", "5.1-6"), ("oc-user", "1.0", "2.0")]);
+    assert_eq!(plan.install.iter().map(|p| &p.package[..]).collect::<Vec<_>>(), vec!["gawk", "libmpfr6"]);
+    assert_eq!(plan.remove.iter().map(|r| (&r.package[..], &r.reason[..])).collect::<Vec<_>>(),
+        vec![("mawk", "oc-user conflicts with mawk")]);
+    assert_eq!(plan.kept_back.iter().map(|k| &k.package[..]).collect::<Vec<_>>(), vec!["kp-lib", "vc-lib"]);
+    assert!(!upgrades(&plan).iter().any(|(package, _, _)| *package == "liblzma5"));
+}
+
+#[test]
+fn pre_dependencies_keep_back_upgrades_too() {
+    let plan = fixtures().plan_upgrade(false);
+    let kp_lib = plan.kept_back.iter().find(|k| k.package == "kp-lib").unwrap();
+    assert_eq!(kp_lib.reason, "kp-user 1.0 pre-depends on \"kp-lib (<< 2.0)\", which wouldn't be satisfied");
+}
@@ -0,0 +1,53 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::Packages;
+use rpkg::packages::{alternative_policy, PolicyContext, POLICY_NAMES};
+
+// the alternatives of package_name's first Depends clause, ranked by the policy called name, with the packages of
+// installed installed (at their available version) and those of selected picked already
+fn ranked<'a>(packages: &'a Packages, name: &str, package_name: &str, installed: &[&str], selected: &[&str]) -> Vec<&'a str> {
+    let policy = alternative_policy(name).unwrap();
+    let installed = |p| {
+        let name = packages.get_package_name(p);
+        if installed.contains(&name) { packages.get_available_debver(name) } else { None }
+    };
+    let selected = |p| selected.contains(&packages.get_package_name(p));
+    let context = PolicyContext { packages, installed : &installed, selected : &selected };
+    let dep = &packages.get_dependencies(package_name).unwrap()[0];
+    return policy.rank(&context, dep).into_iter().map(|p| packages.get_package_name(p)).collect();
+}
+
+#[test]
+fn each_policy_ranks_the_alternatives_its_way() {
+    let packages = fixtures();
+    // awk depends on "mawk | gawk"
+    assert_eq!(ranked(&packages, "first-listed", "awk", &["gawk"], &["gawk"]), vec!["mawk", "gawk"]);
+    assert_eq!(ranked(&packages, "prefer-installed", "awk", &["gawk"], &[]), vec!["gawk", "mawk"]);
+    assert_eq!(ranked(&packages, "prefer-installed", "awk", &[], &["gawk"]), vec!["mawk", "gawk"]);
+    assert_eq!(ranked(&packages, "prefer-already-selected", "awk", &[], &["gawk"]), vec!["gawk", "mawk"]);
+    // wn-app depends on "wn-lib | wn-old (>= 2.0)"
+    assert_eq!(ranked(&packages, "highest-version", "wn-app", &[], &[]), vec!["wn-old", "wn-lib"]);
+    // bt-root depends on "bt-x | bt-y"; bt-x brings in nothing-here and bt-w, bt-y brings in vc-lib unless it's installed
+    assert_eq!(ranked(&packages, "smallest-closure", "bt-root", &["vc-lib"], &[]), vec!["bt-y", "bt-x"]);
+    // a tie goes to the first listed
+    assert_eq!(ranked(&packages, "smallest-closure", "bt-root", &["bt-w"], &[]), vec!["bt-x", "bt-y"]);
+}
+
+#[test]
+fn policies_are_looked_up_by_name() {
+    assert!(POLICY_NAMES.iter().all(|name| alternative_policy(name).is_ok()));
+    assert!(alternative_policy("cheapest").err().unwrap().contains("expected one of first-listed"));
+}
+
+#[test]
+fn the_policy_decides_the_transitive_solution() {
+    let packages = fixtures();
+    let solution = |policy: &str| packages.transitive_dep_solution_with("bt-root", &*alternative_policy(policy).unwrap())
+        .into_iter().map(|p| packages.get_package_name(p)).collect::<Vec<_>>();
+    let (first, smallest) = (solution("first-listed"), solution("smallest-closure"));
+    assert!(first.contains(&"bt-x") && !first.contains(&"bt-y"));
+    assert!(smallest.contains(&"bt-y") && !smallest.contains(&"bt-x"));
+}
@@ -0,0 +1,62 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+
+fn installs(plan: &rpkg::packages::SolverPlan) -> Vec<(&str, &str)> {
+    return plan.install.iter().map(|p| (&p.package[..], &p.version[..])).collect();
+}
+
+#[test]
+fn installs_what_the_request_needs() {
+    let plan = fixtures().solve_install("3depict").unwrap();
+    assert!(plan.satisfiable);
+    assert_eq!(installs(&plan), vec![("3depict", "0.0.23-1"), ("libftgl2", "2.4.0-2"), ("libfreetype6", "2.11.1+dfsg-1")]);
+    assert!(plan.upgrade.is_empty());
+}
+
+#[test]
+fn goes_back_on_an_alternative_that_cant_be_installed() {
+    // bt-x, the first alternative of bt-root's "bt-x | bt-y", depends on a package that doesn't exist
+    let plan = fixtures().solve_install("bt-root").unwrap();
+    assert!(plan.satisfiable);
+    assert_eq!(installs(&plan), vec![("bt-root", "1.0"), ("bt-y", "1.0"), ("bt-z", "1.0")]);
+    assert_eq!(plan.upgrade.iter().map(|c| (&c.package[..], &c.from[..], &c.to[..])).collect::<Vec<_>>(), vec![("vc-lib", "1.0", "2.0")]);
+}
+
+#[test]
+fn explains_a_conflict_with_a_minimal_set_of_constraints() {
+    let plan = fixtures().solve_install("vc-root").unwrap();
+    assert!(!plan.satisfiable);
+    assert!(plan.install.is_empty());
+    // leaving out any one of these makes the rest satisfiable; in particular, nothing about the other packages
+    assert_eq!(plan.conflict, vec![
+        "vc-root is requested",
+        "vc-root 1.0 depends on vc-a",
+        "vc-root 1.0 depends on vc-b",
+        "vc-a 1.0 depends on vc-lib (>= 2.0)",
+        "vc-b 1.0 depends on vc-lib (<< 2.0)",
+        "only one version of vc-lib can be installed (1.0 is installed, 2.0 is available)",
+    ]);
+}
+
+#[test]
+fn unknown_packages_are_an_error() {
+    let e = fixtures().solve_install("no-such").err().unwrap();
+    assert_eq!(e.package, "no-such");
+}
+
+#[test]
+fn pre_dependencies_are_constraints_too() {
+    let plan = fixtures().solve_install("pd-app").unwrap();
+    assert!(!plan.satisfiable);
+    assert_eq!(plan.conflict, vec![
+        "pd-app is requested",
+        "pd-app 1.0 depends on pd-lib",
+        "pd-lib 1.0 pre-depends on pd-missing (no available or installed version satisfies this)",
+    ]);
+    let plan = fixtures().solve_install("cyc-a").unwrap();
+    assert!(plan.satisfiable);
+    assert_eq!(installs(&plan).len(), 3);
+}
@@ -0,0 +1,40 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::fs;
+
+use common::fixtures;
+use rpkg::Packages;
+
+#[test]
+fn common_typos_are_matched() {
+    let packages = fixtures();
+    // a transposition, a missing letter, an extra letter and a wrong one
+    for typo in ["bsah", "gwk", "gawkk", "libc7"].iter() {
+        let suggestions = packages.no_such_package(typo).suggestions;
+        assert!(!suggestions.is_empty(), "no suggestion for {}", typo);
+    }
+    assert_eq!(packages.no_such_package("bsah").suggestions[0], "bash");
+    assert_eq!(packages.no_such_package("gwk").suggestions[0], "gawk");
+    assert_eq!(packages.no_such_package("gawkk").suggestions[0], "gawk");
+    assert_eq!(packages.no_such_package("libc7").suggestions[0], "libc6");
+    assert!(packages.no_such_package("zzzzzzzz").suggestions.is_empty());
+}
+
+#[test]
+fn a_crowd_of_names_sharing_as_few_grams_doesnt_hide_the_match() {
+    // "bsah" only shares its leading "b" with "bash", as it does with every other b* name; these all come first
+    let mut index = String::new();
+    for i in 0..2500 {
+        index.push_str(&format!("Package: b{:04}\nVersion: 1.0\n\n", i));
+    }
+    index.push_str("Package: bash\nVersion: 5.1-6\n");
+    let path = std::env::temp_dir().join(format!("rpkg-suggest-{}", std::process::id()));
+    fs::write(&path, index).unwrap();
+    let mut packages = Packages::new();
+    let parsed = packages.parse_packages(path.to_str().unwrap());
+    fs::remove_file(&path).unwrap();
+    assert_eq!(parsed.unwrap(), 2501);
+    assert_eq!(packages.no_such_package("bsah").suggestions, vec!["bash"]);
+}
@@ -0,0 +1,31 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+
+#[test]
+fn ranks_by_all_reverse_dependencies() {
+    let packages = fixtures();
+    let report = packages.top_depended(false, 3);
+    assert_eq!(report.packages, packages.get_package_names().into_iter().filter(|n| packages.get_available_debver(n).is_some()).count());
+    let ranked : Vec<(&str, usize, usize)> = report.ranked.iter().map(|r| (&r.package[..], r.reverse_dependencies, r.direct)).collect();
+    assert_eq!(ranked.iter().map(|r| r.0).collect::<Vec<_>>(), vec!["libc6", "gcc-11-base", "libgcc-s1"]);
+    // libc6 and libgcc-s1 depend on each other, so whatever needs one of the three needs them all
+    assert!(ranked.iter().all(|r| r.1 == ranked[0].1));
+    assert_eq!((ranked[1].2, ranked[2].2), (2, 2));
+}
+
+#[test]
+fn counts_pre_dependencies_and_stays_within_the_set() {
+    let packages = fixtures();
+    let all = packages.top_depended(false, 100);
+    let count = |name: &str| all.ranked.iter().find(|r| r.package == name).map(|r| (r.reverse_dependencies, r.direct));
+    // only bash, through its Pre-Depends, needs libtinfo6
+    assert_eq!(count("libtinfo6"), Some((1, 1)));
+    assert!(all.ranked.windows(2).all(|w| w[0].reverse_dependencies >= w[1].reverse_dependencies));
+
+    let installed = packages.top_depended(true, 100);
+    assert_eq!(installed.packages, packages.get_package_names().into_iter().filter(|n| packages.get_installed_debver(n).is_some()).count());
+    assert!(installed.ranked.iter().all(|r| packages.get_installed_debver(&r.package).is_some()));
+}
@@ -0,0 +1,48 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::{FirstListed, PlacedConstraint, VersionedDepSolution};
+
+fn solve(package_name: &str) -> VersionedDepSolution {
+    return fixtures().versioned_dep_solution(package_name, &FirstListed).unwrap();
+}
+
+fn placed(c: &PlacedConstraint) -> String {
+    return format!("{} (from {})", c.constraint, c.by);
+}
+
+#[test]
+fn constraints_are_collected_and_checked_against_the_available_versions() {
+    let solution = solve("vapp");
+    assert!(!solution.consistent);
+    let names : Vec<&str> = solution.solution.iter().map(|p| &p.package[..]).collect();
+    assert_eq!(names, vec!["liba", "libb", "libv", "libd"]);
+    let libv = &solution.solution[2];
+    assert_eq!(libv.constraints.iter().map(placed).collect::<Vec<_>>(),
+        vec![">= 2.10 (from vapp)", ">= 2.30 (from liba)", "<< 2.20 (from libb)"]);
+    assert_eq!(libv.unmet.iter().map(placed).collect::<Vec<_>>(), vec!["<< 2.20 (from libb)"]);
+    assert_eq!(libv.conflicting.iter().map(|(a, b)| (placed(a), placed(b))).collect::<Vec<_>>(),
+        vec![(">= 2.30 (from liba)".to_string(), "<< 2.20 (from libb)".to_string())]);
+    assert_eq!(solution.solution[3].unmet.iter().map(placed).collect::<Vec<_>>(), vec![">= 3 (from liba)"]);
+    assert!(fixtures().versioned_dep_solution("no-such-package", &FirstListed).is_err());
+}
+
+#[test]
+fn alternatives_whose_available_version_fails_are_passed_over() {
+    // pv-app depends on "pv-lib (>= 2.0) | pv-alt", and only pv-lib 1.5 is available
+    let solution = solve("pv-app");
+    assert!(solution.consistent);
+    assert_eq!(solution.solution.iter().map(|p| (&p.package[..], &p.pulled_in_by[..])).collect::<Vec<_>>(), vec![("pv-alt", "pv-app")]);
+}
+
+#[test]
+fn pre_dependencies_place_constraints_too() {
+    // kp-user pre-depends on "kp-lib (<< 2.0)", and kp-lib 2.0 is available
+    let solution = solve("kp-user");
+    assert!(!solution.consistent);
+    assert_eq!(solution.solution.len(), 1);
+    assert_eq!(solution.solution[0].package, "kp-lib");
+    assert_eq!(solution.solution[0].unmet.iter().map(placed).collect::<Vec<_>>(), vec!["<< 2.0 (from kp-user)"]);
+}
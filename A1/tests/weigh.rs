@@ -0,0 +1,43 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::{FirstListed, Weight};
+
+fn weigh(package_name: &str, top: Option<usize>) -> Weight {
+    return fixtures().weigh(package_name, &FirstListed, top).unwrap();
+}
+
+#[test]
+fn dependencies_are_ranked_by_what_they_dominate() {
+    let weight = weigh("3depict", Some(3));
+    assert_eq!(weight.closure, 13);
+    let top : Vec<(&str, usize, Vec<&str>)> = weight.dependencies.iter()
+        .map(|d| (&d.package[..], d.packages, d.only_through.iter().map(|p| &p[..]).collect())).collect();
+    assert_eq!(top, vec![
+        ("libxml2", 3, vec!["libicu67", "liblzma5"]),
+        ("libftgl2", 3, vec!["libfreetype6", "libpng16-16"]),
+        ("libc6", 2, vec!["libcrypt1"]),
+    ]);
+}
+
+#[test]
+fn every_dependency_dominates_itself_and_what_only_it_leads_to() {
+    let weight = weigh("3depict", None);
+    assert_eq!(weight.dependencies.len(), weight.closure);
+    assert!(weight.dependencies.iter().all(|d| d.packages == d.only_through.len() + 1));
+    assert!(weight.dependencies.windows(2).all(|w| (w[0].packages, w[0].installed_size_kib) >= (w[1].packages, w[1].installed_size_kib)));
+    // libc6 is shared by everything, so nothing else dominates it
+    assert!(weight.dependencies.iter().all(|d| !d.only_through.contains(&"libc6".to_string())));
+    assert!(weight.installed_size_kib >= weight.dependencies.iter().map(|d| d.installed_size_kib).max().unwrap());
+}
+
+#[test]
+fn pre_dependencies_are_weighed_too() {
+    // bash pre-depends on libc6 and libtinfo6, and depends on base-files and debianutils
+    let weight = weigh("bash", None);
+    let names : Vec<&str> = weight.dependencies.iter().map(|d| &d.package[..]).collect();
+    assert!(names.contains(&"libc6") && names.contains(&"libtinfo6"), "{:?}", names);
+    assert_eq!(weight.dependencies.len(), weight.closure);
+}
@@ -0,0 +1,49 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::WhyReport;
+
+fn chains(report: &WhyReport) -> Vec<Vec<&str>> {
+    return report.paths.iter().map(|path| path.iter().map(|step| &step.chosen[..]).collect()).collect();
+}
+
+#[test]
+fn chains_lead_from_the_root_to_the_target() {
+    let packages = fixtures();
+    assert_eq!(chains(&packages.why("needs-gawk", "libmpfr6", 5).unwrap()), vec![vec!["gawk", "libmpfr6"]]);
+    assert_eq!(packages.why("needs-gawk", "libc6", 5).unwrap().paths.len(), 2);
+    assert!(packages.why("libc6", "needs-gawk", 5).unwrap().paths.is_empty());
+}
+
+#[test]
+fn chains_come_shortest_first_up_to_the_limit() {
+    let packages = fixtures();
+    let all = packages.why("3depict", "libc6", 100).unwrap();
+    assert!(!all.limit_reached);
+    assert!(all.paths.len() > 3);
+    assert!(all.paths.windows(2).all(|w| w[0].len() <= w[1].len()));
+
+    let some = packages.why("3depict", "libc6", 3).unwrap();
+    assert!(some.limit_reached);
+    let lengths : Vec<usize> = some.paths.iter().map(|path| path.len()).collect();
+    assert_eq!(lengths, all.paths[..3].iter().map(|path| path.len()).collect::<Vec<_>>());
+    assert_eq!(chains(&packages.why("3depict", "libc6", 1).unwrap()), vec![vec!["libc6"]]);
+}
+
+#[test]
+fn alternatives_of_the_wrong_version_lead_nowhere() {
+    let packages = fixtures();
+    assert!(packages.why("wn-app", "wn-old", 5).unwrap().paths.is_empty());
+    assert_eq!(chains(&packages.why("wn-app", "wn-lib", 5).unwrap()), vec![vec!["wn-lib"]]);
+}
+
+#[test]
+fn chains_go_through_pre_dependencies() {
+    // bash pre-depends on "libc6 (>= 2.25), libtinfo6 (>= 6)"
+    let report = fixtures().why("bash", "libtinfo6", 5).unwrap();
+    assert_eq!(chains(&report), vec![vec!["libtinfo6"]]);
+    assert!(report.paths[0][0].pre_depends);
+    assert_eq!(report.paths[0][0].dependency, "libtinfo6 (>= 6)");
+}
@@ -0,0 +1,49 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::fixtures;
+use rpkg::packages::{AlternativeCause, UnsatisfiedClause};
+
+fn clause<'a>(report: &'a [UnsatisfiedClause], dependency: &str) -> &'a UnsatisfiedClause {
+    return report.iter().find(|c| c.dependency == dependency).unwrap();
+}
+
+#[test]
+fn alternatives_are_explained_down_to_what_blocks_them() {
+    let report = fixtures().why_not("wn-app").unwrap();
+    assert_eq!(report.version.as_deref(), Some("1.0"));
+    let wn = clause(&report.unsatisfied, "wn-lib | wn-old (>= 2.0)");
+    assert!(wn.impossible);
+    let (wn_lib, wn_old) = (&wn.alternatives[0], &wn.alternatives[1]);
+    assert!(matches!(&wn_lib.cause, AlternativeCause::NotInstalled { available } if available == "1.0"));
+    assert!(!wn_lib.usable);
+    let zlib = clause(&wn_lib.blocked_by, "zlib1g (>= 9:1.0)");
+    assert!(zlib.impossible);
+    assert!(matches!(&zlib.alternatives[0].cause,
+        AlternativeCause::WrongInstalledVersion { installed, available : None, .. } if installed == "1:1.2.11.dfsg-2"));
+    assert!(matches!(&wn_old.cause, AlternativeCause::VersionNotAvailable { available, .. } if available == "1.5"));
+}
+
+#[test]
+fn clauses_that_installing_would_satisfy_are_possible() {
+    let report = fixtures().why_not("bt-root").unwrap();
+    let either = clause(&report.unsatisfied, "bt-x | bt-y");
+    assert!(!either.impossible);
+    let nothing_here = clause(&either.alternatives[0].blocked_by, "nothing-here");
+    assert!(matches!(nothing_here.alternatives[0].cause, AlternativeCause::NoSuchPackage));
+    assert!(either.alternatives[1].usable);
+    assert!(fixtures().why_not("kb-user").unwrap().unsatisfied.is_empty());
+}
+
+#[test]
+fn pre_dependencies_are_explained_too() {
+    let report = fixtures().why_not("pd-app").unwrap();
+    let pd_pre = clause(&report.unsatisfied, "pd-pre");
+    assert!(pd_pre.pre_depends && !pd_pre.impossible);
+    let pd_lib = clause(&report.unsatisfied, "pd-lib");
+    assert!(!pd_lib.pre_depends && pd_lib.impossible);
+    let pd_missing = clause(&pd_lib.alternatives[0].blocked_by, "pd-missing");
+    assert!(pd_missing.pre_depends);
+    assert!(matches!(pd_missing.alternatives[0].cause, AlternativeCause::NoSuchPackage));
+}